@@ -0,0 +1,78 @@
+//! Compare dispatch through the locked handler and through a snapshot
+//!
+//! Run with `cargo bench --bench dispatch`.
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+use std::time::{Duration, Instant};
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+const METHODS: usize = 100;
+const ITERATIONS: u32 = 200_000;
+const MESSAGE: &str = r#"{"jsonrpc": "2.0", "method": "some/method42", "params": [1], "id": 1}"#;
+
+fn measure<F: FnMut()>(name: &str, mut f: F) -> Duration {
+    // Warm up
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = started.elapsed();
+
+    println!(
+        "{:>10}: {:>8} ns/dispatch",
+        name,
+        elapsed.as_nanos() / u128::from(ITERATIONS)
+    );
+    elapsed
+}
+
+fn main() {
+    let handler = JrpcHandler::new().unwrap();
+    for i in 0..METHODS {
+        handler
+            .register_method(format!("some/method{}", i), CopyParams::new().unwrap())
+            .unwrap();
+    }
+    let snapshot = handler.snapshot().unwrap();
+
+    let locked = measure("locked", || {
+        handler
+            .handle_message(MESSAGE)
+            .and_then(|future| future.wait())
+            .unwrap();
+    });
+
+    let lock_free = measure("snapshot", || {
+        snapshot
+            .handle_message(MESSAGE)
+            .and_then(|future| future.wait())
+            .unwrap();
+    });
+
+    println!(
+        "{:>10}: {:.2}x",
+        "speedup",
+        locked.as_secs_f64() / lock_free.as_secs_f64()
+    );
+}
@@ -0,0 +1,133 @@
+//! Attribute macros for [futures-jsonrpc](https://docs.rs/futures-jsonrpc/)
+//!
+//! Enabled in `futures-jsonrpc` with the `derive` feature, and re-exported from there.
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, FnArg, Ident, ItemFn, ReturnType};
+
+/// Turn an `async fn` into a JSON-RPC method
+///
+/// The function receives the params of the request deserialized into its single argument, if
+/// any, and returns `Result<T, JrpcError>` where `T: Serialize`.
+///
+/// For `async fn subtract`, a unit struct `SubtractMethod` implementing `JrpcMethodTrait` is
+/// generated, along with a `subtract_method()` constructor.
+///
+/// ```ignore
+/// #[jrpc_method]
+/// async fn subtract(params: SubtractParams) -> Result<i64, JrpcError> {
+///     Ok(params.minuend - params.subtrahend)
+/// }
+///
+/// handler.register_method("math/subtract", subtract_method())?;
+/// ```
+#[proc_macro_attribute]
+pub fn jrpc_method(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+
+    match expand_method(attr, &function) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_method(attr: TokenStream, function: &ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "jrpc_method doesn't take arguments",
+        ));
+    }
+
+    let signature = &function.sig;
+    if signature.asyncness.is_none() {
+        return Err(syn::Error::new(
+            signature.fn_token.span(),
+            "jrpc_method can only be applied to an async fn",
+        ));
+    }
+
+    if let ReturnType::Default = signature.output {
+        return Err(syn::Error::new(
+            signature.span(),
+            "jrpc_method expects a return type of Result<T, JrpcError>",
+        ));
+    }
+
+    let call_args = match signature.inputs.len() {
+        0 => quote! {},
+        1 => match &signature.inputs[0] {
+            FnArg::Typed(arg) => {
+                let ty = &arg.ty;
+                quote! { ::futures_jsonrpc::compat::params_as::<#ty>(&request)? }
+            }
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new(
+                    receiver.span(),
+                    "jrpc_method can't be applied to methods",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                signature.inputs.span(),
+                "jrpc_method expects at most one argument, receiving the params",
+            ))
+        }
+    };
+
+    let vis = &function.vis;
+    let function_name = &signature.ident;
+    let struct_name = Ident::new(
+        &format!("{}Method", camel_case(&function_name.to_string())),
+        function_name.span(),
+    );
+    let constructor_name = Ident::new(&format!("{}_method", function_name), function_name.span());
+
+    Ok(quote! {
+        #function
+
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #struct_name;
+
+        impl<'r> ::futures_jsonrpc::JrpcMethodTrait<'r> for #struct_name {
+            fn generate_future(
+                &self,
+                request: ::futures_jsonrpc::JrpcRequest,
+            ) -> Result<
+                Box<
+                    dyn 'r
+                        + ::futures_jsonrpc::futures::Future<
+                            Item = Option<::futures_jsonrpc::JrpcResponse>,
+                            Error = ::futures_jsonrpc::ErrorVariant,
+                        >,
+                >,
+                ::futures_jsonrpc::ErrorVariant,
+            > {
+                let future = #function_name(#call_args);
+                Ok(::futures_jsonrpc::compat::method_future(request, future))
+            }
+        }
+
+        #vis fn #constructor_name() -> #struct_name {
+            #struct_name
+        }
+    })
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
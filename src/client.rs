@@ -0,0 +1,229 @@
+use crate::futures::future;
+use crate::futures::prelude::*;
+use crate::futures::sync::oneshot;
+use crate::{ErrorVariant, JrpcRequest, JrpcResponse, JsonValue};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+type Transport<'a> = Box<
+    dyn Fn(
+            String,
+        ) -> Result<
+            Box<dyn 'a + Future<Item = Option<String>, Error = ErrorVariant>>,
+            ErrorVariant,
+        > + Send
+        + Sync
+        + 'a,
+>;
+
+/// Client side of a connection, sending requests through a transport
+///
+/// The transport receives every serialized request and resolves to the serialized reply, if any.
+/// Clones share the transport and the ids, so their calls never reuse an id.
+pub struct JrpcClient<'a> {
+    transport: Arc<Transport<'a>>,
+    ids: Arc<IdGenerator>,
+}
+
+impl<'a> Clone for JrpcClient<'a> {
+    fn clone(&self) -> Self {
+        let transport = self.transport.clone();
+        let ids = self.ids.clone();
+        JrpcClient { transport, ids }
+    }
+}
+
+impl<'a> JrpcClient<'a> {
+    pub fn new<F>(transport: F) -> Self
+    where
+        F: Fn(
+                String,
+            ) -> Result<
+                Box<dyn 'a + Future<Item = Option<String>, Error = ErrorVariant>>,
+                ErrorVariant,
+            > + Send
+            + Sync
+            + 'a,
+    {
+        let transport: Transport<'a> = Box::new(transport);
+        JrpcClient {
+            transport: Arc::new(transport),
+            ids: Arc::new(IdGenerator::new()),
+        }
+    }
+
+    /// Client with a transport replying synchronously, with the serialized response
+    pub fn from_blocking<F>(transport: F) -> Self
+    where
+        F: Fn(String) -> Result<String, ErrorVariant> + Send + Sync + 'a,
+    {
+        JrpcClient::new(move |message: String| {
+            let reply = transport(message).map(Some);
+            let reply: Box<dyn 'a + Future<Item = Option<String>, Error = ErrorVariant>> =
+                Box::new(future::result(reply));
+            Ok(reply)
+        })
+    }
+
+    /// Call `method` with the next id of the client, counting from `1`, resolving to its response
+    pub fn call<T: ToString>(
+        &self,
+        method: T,
+        params: Option<JsonValue>,
+    ) -> Result<Box<dyn 'a + Future<Item = JrpcResponse, Error = ErrorVariant>>, ErrorVariant> {
+        let request = JrpcRequest::new_with_generated_id(&self.ids, method, params)?;
+        self.send_request(request)
+    }
+
+    /// Send `request`, resolving to its response. Fails with `ErrorVariant::NoResponse` if the
+    /// transport gets no reply
+    ///
+    /// The id of the response must match the one of the request, or the future fails with
+    /// `ErrorVariant::IdMismatch`. Error responses with a `null` id, sent by servers that couldn't
+    /// read the id of the request, are returned as is.
+    pub fn send_request(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = JrpcResponse, Error = ErrorVariant>>, ErrorVariant> {
+        let id = request.get_id().clone().unwrap_or(JsonValue::Null);
+        let message = serde_json::to_string(&request)?;
+
+        let future = (self.transport)(message)?.and_then(move |reply| match reply {
+            Some(reply) => JrpcResponse::parse(reply).and_then(|response| {
+                let unidentified = response.get_id().is_null() && response.get_error().is_some();
+                if response.get_id() == &id || unidentified {
+                    Ok(response)
+                } else {
+                    Err(ErrorVariant::IdMismatch(id, response.get_id().clone()))
+                }
+            }),
+            None => Err(ErrorVariant::NoResponse(id)),
+        });
+
+        Ok(Box::new(future))
+    }
+}
+
+/// Source of unique numeric request ids, shareable among threads behind an `Arc`
+///
+/// See [new_with_generated_id](JrpcRequest::new_with_generated_id).
+#[derive(Debug)]
+pub struct IdGenerator {
+    next: AtomicU64,
+}
+
+impl Default for IdGenerator {
+    fn default() -> Self {
+        IdGenerator::starting_at(1)
+    }
+}
+
+impl IdGenerator {
+    /// Generator counting from `1`
+    pub fn new() -> Self {
+        IdGenerator::default()
+    }
+
+    pub fn starting_at(first: u64) -> Self {
+        IdGenerator {
+            next: AtomicU64::new(first),
+        }
+    }
+
+    /// Next id, greater than every id generated before
+    pub fn next_id(&self) -> JsonValue {
+        JsonValue::from(self.next.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Calls sent by a peer that await their responses, for connections where both sides send
+/// requests
+///
+/// Responses are matched to their calls by id, in any order. Clones share the same calls.
+#[derive(Debug, Clone, Default)]
+pub struct PendingCalls {
+    calls: Arc<Mutex<HashMap<String, oneshot::Sender<JrpcResponse>>>>,
+}
+
+impl PendingCalls {
+    pub fn new() -> Self {
+        PendingCalls::default()
+    }
+
+    /// Wait for the response with the id `id`, passed to [resolve](PendingCalls::resolve)
+    ///
+    /// Fails with `ErrorVariant::DuplicateRequestId` if a call with the same id is still pending.
+    /// The future fails with `ErrorVariant::NoResponse` if the call is
+    /// [cancelled](PendingCalls::cancel).
+    pub fn register_call(
+        &self,
+        id: JsonValue,
+    ) -> Result<Box<dyn Future<Item = JrpcResponse, Error = ErrorVariant> + Send>, ErrorVariant>
+    {
+        let key = serde_json::to_string(&id)?;
+        let (sender, receiver) = oneshot::channel();
+
+        {
+            self.calls
+                .lock()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .and_then(|mut calls| {
+                    if calls.contains_key(&key) {
+                        return Err(ErrorVariant::DuplicateRequestId(id.clone()));
+                    }
+                    calls.insert(key, sender);
+                    Ok(())
+                })?;
+        }
+
+        Ok(Box::new(
+            receiver.map_err(move |_| ErrorVariant::NoResponse(id)),
+        ))
+    }
+
+    /// Complete the call with the id of `response`
+    ///
+    /// Fails with `ErrorVariant::UnmatchedResponse` if no call with that id is pending.
+    pub fn resolve(&self, response: JrpcResponse) -> Result<(), ErrorVariant> {
+        let key = serde_json::to_string(response.get_id())?;
+
+        let sender = {
+            self.calls
+                .lock()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut calls| calls.remove(&key))?
+        };
+
+        match sender {
+            // The caller may have dropped its future, no longer waiting for the response
+            Some(sender) => {
+                let _ = sender.send(response);
+                Ok(())
+            }
+            None => Err(ErrorVariant::UnmatchedResponse(response.get_id().clone())),
+        }
+    }
+
+    /// Stop waiting for the response with the id `id`, returning whether it was pending
+    pub fn cancel(&self, id: &JsonValue) -> Result<bool, ErrorVariant> {
+        let key = serde_json::to_string(id)?;
+
+        self.calls
+            .lock()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+            .map(|mut calls| calls.remove(&key).is_some())
+    }
+
+    /// Number of calls awaiting their responses
+    pub fn len(&self) -> Result<usize, ErrorVariant> {
+        self.calls
+            .lock()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+            .map(|calls| calls.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool, ErrorVariant> {
+        self.len().map(|len| len == 0)
+    }
+}
@@ -0,0 +1,74 @@
+//! Adapters to write methods with `async` code on top of the futures 0.1 handler
+use crate::futures::future;
+use crate::futures::prelude::*;
+use crate::{ErrorVariant, JrpcError, JrpcHandler, JrpcRequest, JrpcResponse, JrpcResponseParam};
+use futures03::compat::Future01CompatExt;
+use futures03::future::TryFutureExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future as StdFuture;
+
+/// Deserialize the whole params of the request into `T`
+///
+/// Absent params are handled as `null`, so `()` and `Option<_>` are accepted for methods without
+/// params.
+pub fn params_as<T: DeserializeOwned>(request: &JrpcRequest) -> Result<T, ErrorVariant> {
+    request.get_params_as()
+}
+
+/// Wrap a `std` future into a method future that replies to `request`
+///
+/// `Ok` values are serialized as the result of the response, and `Err` values are sent as the
+/// error object. Notifications resolve to `None`.
+pub fn method_future<'a, T, F>(
+    request: JrpcRequest,
+    future: F,
+) -> Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>
+where
+    T: Serialize,
+    F: 'a + StdFuture<Output = Result<T, JrpcError>>,
+{
+    let future = async move {
+        let response = match future.await {
+            Ok(result) => serde_json::to_value(result)
+                .map_err(ErrorVariant::from)
+                .and_then(JrpcResponseParam::generate_result)?,
+            Err(error) => JrpcResponseParam::generate_error(error)?,
+        };
+
+        if request.is_notification() {
+            return Ok(None);
+        }
+
+        request.generate_response(response).map(Some)
+    };
+
+    Box::new(Box::pin(future).compat())
+}
+
+/// Box a `std` future resolving to the response of a method as the future of the method
+///
+/// Used by [generate_async_method](crate::generate_async_method).
+pub fn response_future<'a, F>(
+    future: F,
+) -> Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>
+where
+    F: 'a + StdFuture<Output = Result<Option<JrpcResponse>, ErrorVariant>>,
+{
+    Box::new(Box::pin(future).compat())
+}
+
+impl<'a> JrpcHandler<'a> {
+    /// Parse and dispatch a message as [handle_message](JrpcHandler::handle_message), returning
+    /// a `std` future to `.await`
+    ///
+    /// Failures to dispatch the message resolve to `Err` as well.
+    pub fn handle_message_async<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> impl 'a + StdFuture<Output = Result<Option<JrpcResponse>, ErrorVariant>> {
+        future::result(self.handle_str(message.as_ref()))
+            .flatten()
+            .compat()
+    }
+}
@@ -0,0 +1,220 @@
+//! Content-encoding negotiation for transports carrying JSON-RPC messages in bodies
+//!
+//! Transports such as HTTP hand the raw body, along with the `Content-Encoding` and
+//! `Accept-Encoding` headers of the request, to [handle_compressed_message], and send the reply
+//! with the returned encoding.
+use crate::futures::future;
+use crate::futures::prelude::*;
+use crate::{ErrorVariant, JrpcError, JrpcErrorEnum, JrpcHandler, JrpcResponse, JsonValue};
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use std::io::{Error as IoError, ErrorKind, Read, Write};
+
+/// Replies shorter than this many bytes are sent uncompressed, since the framing overhead of gzip
+/// and deflate outweighs the savings on small messages
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Default limit in bytes of a decoded body, so a small compressed body can't expand into an
+/// arbitrarily large message
+pub const MAX_DECODED_LEN: usize = 16 * 1024 * 1024;
+
+/// Encoded reply, along with the encoding to advertise in its `Content-Encoding` header
+pub type EncodedReply = (ContentEncoding, Vec<u8>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Identity,
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Parse the value of a `Content-Encoding` header. Fails with
+    /// `ErrorVariant::UnsupportedEncoding` for anything but `identity`, `gzip` and `deflate`
+    pub fn from_header(value: &str) -> Result<Self, ErrorVariant> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "identity" => Ok(ContentEncoding::Identity),
+            "gzip" | "x-gzip" => Ok(ContentEncoding::Gzip),
+            "deflate" => Ok(ContentEncoding::Deflate),
+            other => Err(ErrorVariant::UnsupportedEncoding(other.to_string())),
+        }
+    }
+
+    /// Pick the encoding of the reply from the value of an `Accept-Encoding` header
+    ///
+    /// Gzip is preferred over deflate, and encodings with a quality of zero are ignored.
+    pub fn negotiate(accept_encoding: &str) -> Self {
+        let accepted: Vec<ContentEncoding> = accept_encoding
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let encoding = parts.next()?;
+                let refused = parts.any(|param| {
+                    param
+                        .trim()
+                        .strip_prefix("q=")
+                        .and_then(|q| q.trim().parse::<f32>().ok())
+                        .map(|q| q <= 0.0)
+                        .unwrap_or(false)
+                });
+
+                if refused {
+                    None
+                } else {
+                    ContentEncoding::from_header(encoding).ok()
+                }
+            })
+            .collect();
+
+        [ContentEncoding::Gzip, ContentEncoding::Deflate]
+            .iter()
+            .find(|encoding| accepted.contains(encoding))
+            .cloned()
+            .unwrap_or(ContentEncoding::Identity)
+    }
+
+    /// Value of the `Content-Encoding` header for this encoding
+    pub fn as_header(self) -> &'static str {
+        match self {
+            ContentEncoding::Identity => "identity",
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+
+    /// Decode `body`, limited to [MAX_DECODED_LEN] bytes
+    pub fn decode(self, body: &[u8]) -> Result<String, ErrorVariant> {
+        self.decode_limited(body, MAX_DECODED_LEN)
+    }
+
+    /// Decode `body`, failing with `ErrorVariant::MessageTooLarge` if the decoded body is longer
+    /// than `max_decoded_len` bytes, and with `ErrorVariant::IoError` if it's corrupt or not UTF-8
+    ///
+    /// Decoding stops as soon as the limit is exceeded.
+    pub fn decode_limited(
+        self,
+        body: &[u8],
+        max_decoded_len: usize,
+    ) -> Result<String, ErrorVariant> {
+        let limit = max_decoded_len as u64 + 1;
+        let mut message = vec![];
+
+        match self {
+            ContentEncoding::Identity => body.take(limit).read_to_end(&mut message)?,
+            ContentEncoding::Gzip => GzDecoder::new(body).take(limit).read_to_end(&mut message)?,
+            ContentEncoding::Deflate => DeflateDecoder::new(body)
+                .take(limit)
+                .read_to_end(&mut message)?,
+        };
+
+        if message.len() > max_decoded_len {
+            return Err(ErrorVariant::MessageTooLarge(max_decoded_len));
+        }
+
+        String::from_utf8(message)
+            .map_err(|e| ErrorVariant::IoError(IoError::new(ErrorKind::InvalidData, e)))
+    }
+
+    pub fn encode(self, message: &str) -> Result<Vec<u8>, ErrorVariant> {
+        match self {
+            ContentEncoding::Identity => Ok(message.as_bytes().to_vec()),
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], Compression::default());
+                encoder.write_all(message.as_bytes())?;
+                Ok(encoder.finish()?)
+            }
+            ContentEncoding::Deflate => {
+                let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+                encoder.write_all(message.as_bytes())?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+}
+
+/// Compress `reply` with `encoding`, unless it is shorter than [COMPRESSION_THRESHOLD]
+fn encode_reply(reply: String, encoding: ContentEncoding) -> Result<EncodedReply, ErrorVariant> {
+    let encoding = if reply.len() < COMPRESSION_THRESHOLD {
+        ContentEncoding::Identity
+    } else {
+        encoding
+    };
+
+    encoding.encode(&reply).map(|body| (encoding, body))
+}
+
+/// Handle a possibly compressed body, single or batch, and encode the reply
+///
+/// The body is decoded according to `content_encoding` before reaching the handler. The reply is
+/// compressed with the encoding negotiated from `accept_encoding`, unless it is shorter than
+/// [COMPRESSION_THRESHOLD]. Notifications resolve to `None`.
+///
+/// The decoded body is limited to [MAX_DECODED_LEN] bytes, see
+/// [handle_compressed_message_with_limit].
+pub fn handle_compressed_message<'a>(
+    handler: &JrpcHandler<'a>,
+    body: &[u8],
+    content_encoding: Option<&str>,
+    accept_encoding: Option<&str>,
+) -> Result<Box<dyn 'a + Future<Item = Option<EncodedReply>, Error = ErrorVariant>>, ErrorVariant> {
+    handle_compressed_message_with_limit(
+        handler,
+        body,
+        content_encoding,
+        accept_encoding,
+        MAX_DECODED_LEN,
+    )
+}
+
+/// Handle a possibly compressed body as [handle_compressed_message], with the decoded body
+/// limited to `max_decoded_len` bytes
+///
+/// An oversize body fails with `ErrorVariant::MessageTooLarge`, for the transport to refuse it.
+/// A corrupt body, or one that is not UTF-8, is answered with a parse error (`-32700`).
+pub fn handle_compressed_message_with_limit<'a>(
+    handler: &JrpcHandler<'a>,
+    body: &[u8],
+    content_encoding: Option<&str>,
+    accept_encoding: Option<&str>,
+    max_decoded_len: usize,
+) -> Result<Box<dyn 'a + Future<Item = Option<EncodedReply>, Error = ErrorVariant>>, ErrorVariant> {
+    let decoded = content_encoding
+        .map(ContentEncoding::from_header)
+        .unwrap_or(Ok(ContentEncoding::Identity))?
+        .decode_limited(body, max_decoded_len);
+    let encoding = accept_encoding
+        .map(ContentEncoding::negotiate)
+        .unwrap_or(ContentEncoding::Identity);
+
+    let message = match decoded {
+        Ok(message) => message,
+        Err(ErrorVariant::IoError(e)) => {
+            debug!("Undecodable body: {}", e);
+            let error = JrpcError::from(JrpcErrorEnum::ParseError);
+            let reply = JrpcResponse::new(None, Some(error), JsonValue::Null)?.to_json_string()?;
+            return Ok(Box::new(future::result(
+                encode_reply(reply, encoding).map(Some),
+            )));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let future = handler
+        .handle_batch_message(message)?
+        .and_then(move |outcome| {
+            let outcome = match outcome {
+                Some(outcome) => outcome,
+                None => return future::ok(None),
+            };
+
+            let encoded = serde_json::to_string(&outcome)
+                .map_err(ErrorVariant::from)
+                .and_then(|reply| encode_reply(reply, encoding))
+                .map(Some);
+
+            future::result(encoded)
+        });
+
+    Ok(Box::new(future))
+}
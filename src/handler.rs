@@ -1,42 +1,513 @@
+use crate::futures::future::{self, Either};
 use crate::futures::prelude::*;
-use crate::{ErrorVariant, JrpcMethodTrait, JrpcRequest, JrpcResponse};
-use std::collections::HashMap;
+use crate::futures::stream;
+use crate::futures::sync::oneshot;
+use crate::inflight::{Admission, Idle, InFlight, InFlightGuard};
+use crate::method::{
+    DispatchedMethod, FnMethod, JrpcContext, JrpcNotificationMethodTrait, JrpcOutcomeMethodTrait,
+    JrpcStreamingMethodTrait, NotificationMethod, OutcomeMethod, ParamsStream,
+};
+#[cfg(feature = "openrpc")]
+use crate::openrpc::{self, MethodSchema};
+use crate::params::ParamsSpec;
+use crate::ratelimit::RateLimits;
+use crate::session::InFlightIds;
+use crate::streaming::{self, Head};
+use crate::timeout::{self, Timeouts};
+use crate::{
+    BatchOrder, BatchResponseBuilder, DispatchError, ErrorVariant, JrpcBatchOutcome,
+    JrpcBatchResponse, JrpcError, JrpcErrorEnum, JrpcMethodTrait, JrpcRequest, JrpcResponse,
+    JsonRawValue, JsonValue,
+};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::iter::FromIterator;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
+type InternalErrorHook<'a> = Arc<dyn Fn(&str, &ErrorVariant) + Send + Sync + 'a>;
+type NotificationHook<'a> = Arc<dyn Fn(&JrpcRequest) + Send + Sync + 'a>;
+type PreHook<'a> = Arc<dyn Fn(&JrpcRequest) -> Result<(), JrpcError> + Send + Sync + 'a>;
+type PostHook<'a> = Arc<dyn Fn(&JrpcRequest, &JrpcResponse) + Send + Sync + 'a>;
+type MethodFilter<'a> = Arc<dyn Fn(&str) -> bool + Send + Sync + 'a>;
+type MethodMap<'a> = HashMap<String, Arc<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>>;
+type StreamingMethodMap<'a> =
+    HashMap<String, Arc<dyn JrpcStreamingMethodTrait<'a> + Send + Sync + 'a>>;
+type RawPreprocessor<'a> = Arc<dyn Fn(String) -> Result<String, JrpcError> + Send + Sync + 'a>;
+
+/// Registry of methods, dispatching messages to them
+///
+/// The state of the handler is kept behind `Arc`s, so clones are cheap and share the same
+/// methods and settings: a method registered through one clone is seen by all of them. The
+/// handler is `Send + Sync`, so clones can be moved to other threads to dispatch concurrently.
+#[derive(Clone)]
 pub struct JrpcHandler<'a> {
-    hm_methods: Arc<RwLock<HashMap<String, Box<dyn JrpcMethodTrait<'a> + 'a>>>>,
+    hm_methods: Arc<RwLock<MethodMap<'a>>>,
+    streaming_methods: Arc<RwLock<StreamingMethodMap<'a>>>,
+    #[cfg(feature = "openrpc")]
+    schemas: Arc<RwLock<HashMap<String, MethodSchema>>>,
+    config: Arc<RwLock<HandlerConfig<'a>>>,
+    rate_limits: Arc<RateLimits>,
+    inflight: Arc<InFlight>,
+}
+
+/// Settings of a handler, shared by all its clones and copied into its snapshots
+#[derive(Clone, Default)]
+struct HandlerConfig<'a> {
+    default_method: Option<Arc<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>>,
+    internal_error_hook: Option<InternalErrorHook<'a>>,
+    notification_hook: Option<NotificationHook<'a>>,
+    pre_hooks: Vec<PreHook<'a>>,
+    post_hooks: Vec<PostHook<'a>>,
+    method_filter: Option<MethodFilter<'a>>,
+    raw_preprocessor: Option<RawPreprocessor<'a>>,
+    strict_mode: bool,
+    preserve_raw_ids: bool,
+    unique_ids: bool,
+    attach_signatures: bool,
+    lenient_version: bool,
+    capture_backtraces: bool,
+    error_categories: bool,
+    error_namespaces: Vec<(String, i32)>,
+    timeouts: Timeouts,
+    priorities: HashMap<String, i32>,
+    params_specs: HashMap<String, ParamsSpec>,
+    catching_methods: HashSet<String>,
 }
 
-impl<'a> Clone for JrpcHandler<'a> {
-    fn clone(&self) -> Self {
-        let hm_methods = self.hm_methods.clone();
-        JrpcHandler { hm_methods }
+impl<'a> HandlerConfig<'a> {
+    /// Parse `message` as set by [set_lenient_version](JrpcHandler::set_lenient_version) and
+    /// [set_preserve_raw_ids](JrpcHandler::set_preserve_raw_ids)
+    fn parse_request(&self, message: &str) -> Result<JrpcRequest, ErrorVariant> {
+        JrpcRequest::parse_with(message, self.lenient_version, self.preserve_raw_ids)
     }
 }
 
 impl<'a> JrpcHandler<'a> {
     pub fn new() -> Result<Self, ErrorVariant> {
         let hm_methods = Arc::new(RwLock::new(HashMap::new()));
-        let handler = JrpcHandler { hm_methods };
+        let streaming_methods = Arc::new(RwLock::new(HashMap::new()));
+        #[cfg(feature = "openrpc")]
+        let schemas = Arc::new(RwLock::new(HashMap::new()));
+        let config = Arc::new(RwLock::new(HandlerConfig::default()));
+        let rate_limits = Arc::new(RateLimits::new());
+        let inflight = Arc::new(InFlight::new());
+        let handler = JrpcHandler {
+            hm_methods,
+            streaming_methods,
+            #[cfg(feature = "openrpc")]
+            schemas,
+            config,
+            rate_limits,
+            inflight,
+        };
+        Ok(handler)
+    }
+
+    /// Change the settings, seen by all clones of the handler
+    fn configure<T, F: FnOnce(&mut HandlerConfig<'a>) -> T>(
+        &self,
+        f: F,
+    ) -> Result<T, ErrorVariant> {
+        self.config
+            .write()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+            .map(|mut config| f(&mut config))
+    }
+
+    /// Read from the settings. The lock is released on return, so hooks cloned out of the
+    /// settings may use the handler when called
+    fn read_config<T, F: FnOnce(&HandlerConfig<'a>) -> T>(&self, f: F) -> Result<T, ErrorVariant> {
+        self.config
+            .read()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+            .map(|config| f(&config))
+    }
+
+    /// Set a hook to be called with the targeted method signature whenever a registered method
+    /// fails with an internal error, either while generating its future or while polling it
+    ///
+    /// Failures caused by the client, such as `ErrorVariant::InvalidParams`, don't call the hook.
+    /// See [DispatchError] for the classification.
+    pub fn on_internal_error<F: Fn(&str, &ErrorVariant) + Send + Sync + 'a>(
+        &self,
+        hook: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let hook: InternalErrorHook<'a> = Arc::new(hook);
+        self.configure(|config| config.internal_error_hook.replace(hook))?;
+        Ok(self)
+    }
+
+    /// Set a hook to be called with every notification dispatched to a registered method
+    ///
+    /// It is called once the method is found, whatever the outcome of the method, so notifications
+    /// can be counted or logged apart from calls.
+    pub fn on_notification<F: Fn(&JrpcRequest) + Send + Sync + 'a>(
+        &self,
+        hook: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let hook: NotificationHook<'a> = Arc::new(hook);
+        self.configure(|config| config.notification_hook.replace(hook))?;
+        Ok(self)
+    }
+
+    /// Add a hook to be called with every request before it is dispatched
+    ///
+    /// Hooks run in the order they were added, and the first one to return `Err` aborts the
+    /// dispatch: the request is answered with the error and the id of the request instead.
+    ///
+    /// Hooks also run in [snapshots](JrpcHandler::snapshot) taken after they were added. While any
+    /// hook is registered, [handle_reader](JrpcHandler::handle_reader) refuses to stream params.
+    pub fn add_pre_hook<F: Fn(&JrpcRequest) -> Result<(), JrpcError> + Send + Sync + 'a>(
+        &self,
+        hook: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let hook: PreHook<'a> = Arc::new(hook);
+        self.configure(|config| config.pre_hooks.push(hook))?;
+        Ok(self)
+    }
+
+    /// Add a hook to be called with every request along with its response, once resolved
+    ///
+    /// Hooks run in the order they were added. They are not called for requests resolving to no
+    /// response, such as notifications.
+    pub fn add_post_hook<F: Fn(&JrpcRequest, &JrpcResponse) + Send + Sync + 'a>(
+        &self,
+        hook: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let hook: PostHook<'a> = Arc::new(hook);
+        self.configure(|config| config.post_hooks.push(hook))?;
+        Ok(self)
+    }
+
+    /// Set a filter consulted before every dispatch. Methods for which it returns `false` stay
+    /// registered, but requests to them fail with `ErrorVariant::MethodDisabled`
+    ///
+    /// Replacing the filter is enough to enable them again.
+    pub fn set_method_filter<F: Fn(&str) -> bool + Send + Sync + 'a>(
+        &self,
+        filter: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let filter: MethodFilter<'a> = Arc::new(filter);
+        self.configure(|config| config.method_filter.replace(filter))?;
+        Ok(self)
+    }
+
+    /// Remove the method filter, allowing every registered method
+    pub fn clear_method_filter(&self) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.method_filter.take())?;
+        Ok(self)
+    }
+
+    /// Set a function applied to every incoming message before it is parsed, e.g. to decompress,
+    /// decrypt or verify it
+    ///
+    /// If it fails, the returned error is sent back in a response with a `null` id, since the
+    /// request was not parsed.
+    pub fn set_raw_preprocessor<F: Fn(String) -> Result<String, JrpcError> + Send + Sync + 'a>(
+        &self,
+        preprocessor: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let preprocessor: RawPreprocessor<'a> = Arc::new(preprocessor);
+        self.configure(|config| config.raw_preprocessor.replace(preprocessor))?;
+        Ok(self)
+    }
+
+    /// In strict mode, a response whose id differs from the id of its request fails with
+    /// `ErrorVariant::IdMismatch`. Otherwise, the mismatch is only logged
+    ///
+    /// This catches methods building their own responses with the wrong id, which clients can't
+    /// correlate with their requests.
+    pub fn set_strict_mode(&self, strict: bool) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.strict_mode = strict)?;
+        Ok(self)
+    }
+
+    /// Parse requests keeping the exact textual form of their ids, so responses built with
+    /// [generate_response](JrpcRequest::generate_response) echo them byte-for-byte
+    ///
+    /// Applies to every entry point, including the elements of a batch.
+    pub fn set_preserve_raw_ids(&self, preserve: bool) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.preserve_raw_ids = preserve)?;
+        Ok(self)
+    }
+
+    /// Start every [JrpcSession](crate::JrpcSession), such as the one created for each connection
+    /// by [serve_duplex](crate::transport::serve_duplex), rejecting requests that reuse the id of a
+    /// request of the same session still in flight. Disabled by default
+    ///
+    /// See [set_enforce_unique_ids](crate::JrpcSession::set_enforce_unique_ids).
+    pub fn set_enforce_unique_ids(&self, enforce: bool) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.unique_ids = enforce)?;
+        Ok(self)
+    }
+
+    /// Attach to each request the key of the method it was dispatched to, read by methods with
+    /// [get_matched_signature](JrpcRequest::get_matched_signature). Disabled by default
+    pub fn set_attach_signatures(&self, attach: bool) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.attach_signatures = attach)?;
+        Ok(self)
+    }
+
+    pub(crate) fn enforces_unique_ids(&self) -> Result<bool, ErrorVariant> {
+        self.read_config(|config| config.unique_ids)
+    }
+
+    /// Accept requests omitting the `jsonrpc` version or sending `"2"`, as sent by some legacy
+    /// peers, see [parse_lenient](JrpcRequest::parse_lenient). Disabled by default
+    pub fn set_lenient_version(&self, lenient: bool) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.lenient_version = lenient)?;
+        Ok(self)
+    }
+
+    /// Attach a backtrace to the `data` of internal errors (`-32603`) answered by the handler
+    ///
+    /// Failures of a method are captured where the handler first observes them, and are also
+    /// logged, since [handle_message](JrpcHandler::handle_message) returns them as the method
+    /// raised them. Only effective in debug builds, see [with_backtrace](JrpcError::with_backtrace).
+    pub fn capture_backtraces(&self, capture: bool) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.capture_backtraces = capture)?;
+        Ok(self)
+    }
+
+    /// Add the category of the code, such as `"InvalidParams"`, to the `data` of every error
+    /// answered by the handler, see [with_category](JrpcError::with_category)
+    ///
+    /// Off by default, leaving the errors as defined by the specification.
+    pub fn set_error_categories(&self, enabled: bool) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.error_categories = enabled)?;
+        Ok(self)
+    }
+
+    /// Move the reserved error codes of methods under `prefix` to an application range starting
+    /// at `base`, so clients can route errors by range
+    ///
+    /// * Only codes of the reserved range, `-32768` to `-32000`, are remapped, to
+    ///   `base + (-32000 - code)`. With a base of `1000`, `-32602` becomes `1602`. Application
+    ///   codes are kept.
+    /// * Both error responses built by the method and the errors its future fails with are
+    ///   remapped, the latter when they're answered by
+    ///   [handle_batch_message](JrpcHandler::handle_batch_message). Errors raised before reaching
+    ///   a method, e.g. `-32601` for an unknown signature, keep their code.
+    /// * If several prefixes match a signature, the longest wins. Setting a prefix again replaces
+    ///   its base.
+    pub fn set_error_namespace<T: ToString>(
+        &self,
+        prefix: T,
+        base: i32,
+    ) -> Result<&Self, ErrorVariant> {
+        let prefix = prefix.to_string();
+
+        self.configure(|config| {
+            config.error_namespaces.retain(|(p, _)| p != &prefix);
+            config.error_namespaces.push((prefix, base));
+        })?;
+        Ok(self)
+    }
+
+    /// Accept at most `max_calls` requests to `signature` per `window`, counted in fixed windows
+    ///
+    /// Requests over the limit fail with `ErrorVariant::RateLimited`, answered with a `-32000`
+    /// error whose data holds the time left in the window, as `{"retry_after_ms": 1500}`. Setting
+    /// a limit again replaces it and starts a new window.
+    pub fn set_rate_limit<T: ToString>(
+        &self,
+        signature: T,
+        max_calls: u32,
+        window: Duration,
+    ) -> Result<&Self, ErrorVariant> {
+        self.rate_limits
+            .set(signature.to_string(), max_calls, window)?;
+        Ok(self)
+    }
+
+    /// Remove the rate limit of `signature`, returning whether there was one
+    pub fn clear_rate_limit<T: ToString>(&self, signature: T) -> Result<bool, ErrorVariant> {
+        self.rate_limits.remove(&signature.to_string())
+    }
+
+    /// Time out requests to methods without a timeout of their own after `timeout`, or never if
+    /// `None`, which is the default
+    ///
+    /// Requests that time out are answered with a `-32000` error, and the future of the method is
+    /// dropped.
+    pub fn set_default_timeout(&self, timeout: Option<Duration>) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.timeouts.set_default(timeout))?;
+        Ok(self)
+    }
+
+    /// Time out requests to `signature` after `timeout`, overriding the default timeout
+    pub fn set_method_timeout<T: ToString>(
+        &self,
+        signature: T,
+        timeout: Duration,
+    ) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.timeouts.set(signature.to_string(), timeout))?;
+        Ok(self)
+    }
+
+    /// Remove the timeout of `signature`, returning whether there was one. Requests to it fall back
+    /// to the default timeout
+    pub fn clear_method_timeout<T: ToString>(&self, signature: T) -> Result<bool, ErrorVariant> {
+        self.configure(|config| config.timeouts.remove(&signature.to_string()))
+    }
+
+    /// Dispatch at most `max` requests at a time, or any number if `None`, which is the default
+    ///
+    /// Requests over the limit are queued until a dispatch in flight resolves or is dropped, and
+    /// then dispatched by priority, see
+    /// [register_method_with_priority](JrpcHandler::register_method_with_priority).
+    pub fn set_max_inflight(&self, max: Option<usize>) -> Result<&Self, ErrorVariant> {
+        self.inflight.set_max(max)?;
+        Ok(self)
+    }
+
+    /// Number of dispatched futures, shared by all clones of this handler, that didn't resolve
+    /// and weren't dropped yet
+    pub fn inflight_count(&self) -> Result<usize, ErrorVariant> {
+        self.inflight.count()
+    }
+
+    /// Future resolving once there are no dispatches in flight
+    ///
+    /// Useful to wait for every method to finish before shutting down, or to synchronize tests.
+    pub fn await_idle(&self) -> Idle {
+        Idle::new(self.inflight.clone())
+    }
+
+    /// Build a handler from `(signature, method)` pairs, failing on the first signature that can't
+    /// be registered
+    pub fn try_from_iter<
+        T: ToString,
+        I: IntoIterator<Item = (T, Box<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>)>,
+    >(
+        iter: I,
+    ) -> Result<Self, ErrorVariant> {
+        let handler = JrpcHandler::new()?;
+
+        for (signature, jrpc_method) in iter {
+            let signature = JrpcHandler::check_signature(signature.to_string())?;
+            handler.insert_shared_method(signature, Arc::from(jrpc_method))?;
+        }
+
         Ok(handler)
     }
 
-    pub fn register_method<T: ToString, F: JrpcMethodTrait<'a> + 'a>(
+    pub fn register_method<T: ToString, F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
         &self,
         signature: T,
         jrpc_method: F,
     ) -> Result<&Self, ErrorVariant> {
-        let signature = signature.to_string();
-        let jrpc_method = Box::new(jrpc_method);
-        let log_message = format!("Signature {} registered as method", &signature);
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+        self.insert_method(signature, jrpc_method)
+    }
+
+    /// Register a synchronous method implemented by a closure, without defining a future
+    ///
+    /// The closure runs when the future of the request is generated.
+    pub fn register_fn<T, F>(&self, signature: T, f: F) -> Result<&Self, ErrorVariant>
+    where
+        T: ToString,
+        F: Fn(JrpcRequest) -> Result<Option<JrpcResponse>, ErrorVariant> + Send + Sync + 'a,
+    {
+        self.register_method(signature, FnMethod(f))
+    }
+
+    /// Register a fire-and-forget method, which never answers
+    ///
+    /// Requests to it carrying an id are answered with a `-32600` error, and the method doesn't
+    /// run.
+    pub fn register_notification<
+        T: ToString,
+        F: JrpcNotificationMethodTrait<'a> + Send + Sync + 'a,
+    >(
+        &self,
+        signature: T,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        self.register_method(signature, NotificationMethod(jrpc_method))
+    }
+
+    /// Register a method whose future resolves to a [MethodOutcome](crate::MethodOutcome) instead
+    /// of an `Option<JrpcResponse>`
+    pub fn register_outcome_method<
+        T: ToString,
+        F: JrpcOutcomeMethodTrait<'a> + Send + Sync + 'a,
+    >(
+        &self,
+        signature: T,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        self.register_method(signature, OutcomeMethod(jrpc_method))
+    }
+
+    /// Register a method whose implementation is picked by `dispatch` for each request
+    ///
+    /// Useful for methods with several forms, e.g. accepting both positional and named params,
+    /// each implemented by its own future.
+    pub fn register_dispatched_method<T, F>(
+        &self,
+        signature: T,
+        dispatch: F,
+    ) -> Result<&Self, ErrorVariant>
+    where
+        T: ToString,
+        F: Fn(&JrpcRequest) -> Box<dyn JrpcMethodTrait<'a> + 'a> + Send + Sync + 'a,
+    {
+        self.register_method(signature, DispatchedMethod(dispatch))
+    }
+
+    /// Set a method receiving every request whose method is not registered, instead of failing
+    /// with `ErrorVariant::MethodSignatureNotFound`
+    ///
+    /// The request keeps the unknown method name, and has no
+    /// [matched signature](JrpcRequest::get_matched_signature). No
+    /// [params spec](JrpcHandler::register_method_with_schema) is checked before it runs, and its
+    /// errors are never caught as with
+    /// [register_method_catching_errors](JrpcHandler::register_method_catching_errors).
+    pub fn set_default_method<F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
+        &self,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let jrpc_method: Arc<dyn JrpcMethodTrait<'a> + Send + Sync + 'a> = Arc::new(jrpc_method);
+
+        self.configure(|config| config.default_method.replace(jrpc_method))?;
+        Ok(self)
+    }
+
+    /// Remove the default method, so requests to unregistered methods fail with
+    /// `ErrorVariant::MethodSignatureNotFound` again
+    pub fn clear_default_method(&self) -> Result<&Self, ErrorVariant> {
+        self.configure(|config| config.default_method.take())?;
+        Ok(self)
+    }
+
+    /// Register a method receiving its array params as a stream, see
+    /// [handle_reader](JrpcHandler::handle_reader)
+    ///
+    /// Streaming methods are only dispatched by `handle_reader`, and only for requests with array
+    /// params.
+    pub fn register_streaming_method<
+        T: ToString,
+        F: JrpcStreamingMethodTrait<'a> + Send + Sync + 'a,
+    >(
+        &self,
+        signature: T,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+        let log_message = format!("Signature {} registered as streaming method", &signature);
 
         {
-            self.hm_methods
-                .try_write()
+            self.streaming_methods
+                .write()
                 .map_err(|_| ErrorVariant::RwLockPoisoned)
-                .and_then(|mut hm| {
-                    hm.insert(signature, jrpc_method);
-                    Ok(())
+                .map(|mut hm| {
+                    hm.insert(signature, Arc::new(jrpc_method));
                 })?;
         }
 
@@ -44,31 +515,1464 @@ impl<'a> JrpcHandler<'a> {
         Ok(self)
     }
 
-    pub fn handle_message<T: ToString>(
+    /// Register a method whose queued requests are dispatched before the ones of methods with a
+    /// lower priority, see [set_max_inflight](JrpcHandler::set_max_inflight)
+    ///
+    /// Methods registered otherwise have a priority of `0`. The priority is kept until the method
+    /// is unregistered.
+    pub fn register_method_with_priority<T: ToString, F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
         &self,
-        message: T,
-    ) -> Result<Box<'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
-    {
-        let message = message.to_string();
-        let log_message = format!("Message {}", &message);
-        let request = JrpcRequest::parse(message)?;
-        let log_message = format!("{} generated response {:?}", &log_message, &request);
+        signature: T,
+        jrpc_method: F,
+        priority: i32,
+    ) -> Result<&Self, ErrorVariant> {
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+
+        self.configure(|config| config.priorities.insert(signature.clone(), priority))?;
+
+        self.insert_method(signature, jrpc_method)
+    }
+
+    /// Register a method whose params are checked against `spec` before dispatching requests to it
+    ///
+    /// Requests with params not matching the spec are answered with a `-32602` error, and the
+    /// method doesn't run. The spec is kept until the method is unregistered.
+    pub fn register_method_with_schema<T: ToString, F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
+        &self,
+        signature: T,
+        jrpc_method: F,
+        spec: ParamsSpec,
+    ) -> Result<&Self, ErrorVariant> {
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+
+        self.configure(|config| config.params_specs.insert(signature.clone(), spec))?;
+
+        self.insert_method(signature, jrpc_method)
+    }
+
+    /// Register a method whose failures are answered with an error response carrying the id of
+    /// the request, instead of failing the future of the dispatch
+    ///
+    /// The error object is built with `From<ErrorVariant> for JrpcError`, so e.g.
+    /// `ErrorVariant::InternalError` is answered with `-32603`. Internal error hooks are still
+    /// called. The behavior is kept until the method is unregistered.
+    pub fn register_method_catching_errors<
+        T: ToString,
+        F: JrpcMethodTrait<'a> + Send + Sync + 'a,
+    >(
+        &self,
+        signature: T,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+
+        self.configure(|config| config.catching_methods.insert(signature.clone()))?;
+
+        self.insert_method(signature, jrpc_method)
+    }
+
+    /// Register a method along with its timeout, see
+    /// [set_method_timeout](JrpcHandler::set_method_timeout)
+    ///
+    /// Requests that time out are answered with a `-32000` error, and notifications resolve to
+    /// `None`. The timeout is kept until cleared with
+    /// [clear_method_timeout](JrpcHandler::clear_method_timeout).
+    pub fn register_method_with_timeout<T: ToString, F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
+        &self,
+        signature: T,
+        jrpc_method: F,
+        timeout: Duration,
+    ) -> Result<&Self, ErrorVariant> {
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+        self.set_method_timeout(signature.clone(), timeout)?;
+        self.insert_method(signature, jrpc_method)
+    }
+
+    /// Register `jrpc_method` only if no method is registered under `signature` yet, returning
+    /// whether it was inserted
+    ///
+    /// The check and the insertion happen under a single write lock, so exactly one of several
+    /// concurrent registrations of the same signature succeeds.
+    pub fn register_method_if_absent<T: ToString, F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
+        &self,
+        signature: T,
+        jrpc_method: F,
+    ) -> Result<bool, ErrorVariant> {
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+        let log_message = format!("Signature {} registered as method", &signature);
 
-        let future = {
+        let inserted = {
             self.hm_methods
-                .try_read()
+                .write()
                 .map_err(|_| ErrorVariant::RwLockPoisoned)
-                .and_then(|hm| {
-                    hm.get(request.get_method())
-                        .map(|method| Ok(method))
-                        .unwrap_or(Err(ErrorVariant::MethodSignatureNotFound(
-                            request.get_method().clone(),
-                        )))
-                        .and_then(|method| method.generate_future(request))
+                .map(|mut hm| match hm.entry(signature) {
+                    Entry::Occupied(_) => false,
+                    Entry::Vacant(entry) => {
+                        entry.insert(Arc::new(jrpc_method));
+                        true
+                    }
                 })?
         };
 
-        trace!("{}", log_message);
-        Ok(future)
+        if inserted {
+            trace!("{}", log_message);
+        }
+        Ok(inserted)
+    }
+
+    /// Register `jrpc_method`, failing with `ErrorVariant::MethodAlreadyRegistered` if a method is
+    /// already registered under `signature`
+    ///
+    /// Unlike [register_method](JrpcHandler::register_method), the existing method is left in
+    /// place, so two parts of an application claiming the same signature are caught.
+    pub fn register_method_checked<T: ToString, F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
+        &self,
+        signature: T,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        let signature = signature.to_string();
+
+        if self.register_method_if_absent(signature.clone(), jrpc_method)? {
+            Ok(self)
+        } else {
+            Err(ErrorVariant::MethodAlreadyRegistered(signature))
+        }
+    }
+
+    /// Register several methods under a single write lock, replacing the ones already registered
+    /// under the same signatures
+    ///
+    /// If any signature is reserved, none of the methods is registered.
+    pub fn register_methods<I>(&self, methods: I) -> Result<&Self, ErrorVariant>
+    where
+        I: IntoIterator<Item = (String, Box<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>)>,
+    {
+        let methods =
+            methods
+                .into_iter()
+                .map(|(signature, jrpc_method)| {
+                    JrpcHandler::check_signature(signature).map(|s| (s, Arc::from(jrpc_method)))
+                })
+                .collect::<Result<
+                    Vec<(String, Arc<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>)>,
+                    ErrorVariant,
+                >>()?;
+
+        {
+            self.hm_methods
+                .write()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut hm| hm.extend(methods.iter().cloned()))?;
+        }
+
+        for (signature, _) in &methods {
+            trace!("Signature {} registered as method", signature);
+        }
+
+        Ok(self)
+    }
+
+    /// Register a method under the `rpc.` prefix, reserved for system extensions such as
+    /// `rpc.discover`
+    pub fn register_system_method<T: ToString, F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
+        &self,
+        signature: T,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        self.insert_method(signature.to_string(), jrpc_method)
+    }
+
+    /// Register `jrpc_method` under `signature` only while `f` runs, then restore the method
+    /// previously registered under it, if any
+    ///
+    /// The previous registration is restored even if `f` panics.
+    pub fn with_temporary_method<T, F, R, C>(
+        &self,
+        signature: T,
+        jrpc_method: F,
+        f: C,
+    ) -> Result<R, ErrorVariant>
+    where
+        T: ToString,
+        F: JrpcMethodTrait<'a> + Send + Sync + 'a,
+        C: FnOnce() -> R,
+    {
+        let signature = JrpcHandler::check_signature(signature.to_string())?;
+
+        let previous = {
+            self.hm_methods
+                .write()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut hm| hm.insert(signature.clone(), Arc::new(jrpc_method)))?
+        };
+        trace!("Signature {} temporarily registered as method", &signature);
+
+        let _guard = TemporaryMethod {
+            handler: self,
+            signature,
+            previous,
+        };
+
+        Ok(f())
+    }
+
+    /// Remove the method registered under `signature`, returning whether there was one
+    ///
+    /// Futures already dispatched to it are not affected.
+    pub fn unregister_method<T: ToString>(&self, signature: T) -> Result<bool, ErrorVariant> {
+        let signature = signature.to_string();
+
+        let removed = {
+            self.hm_methods
+                .write()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut hm| hm.remove(&signature).is_some())?
+        };
+
+        let removed_streaming = {
+            self.streaming_methods
+                .write()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut hm| hm.remove(&signature).is_some())?
+        };
+        let removed = removed || removed_streaming;
+
+        #[cfg(feature = "openrpc")]
+        {
+            self.schemas
+                .write()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut schemas| schemas.remove(&signature))?;
+        }
+
+        self.configure(|config| {
+            config.priorities.remove(&signature);
+            config.params_specs.remove(&signature);
+            config.catching_methods.remove(&signature);
+        })?;
+
+        if removed {
+            trace!("Signature {} unregistered", &signature);
+        }
+        Ok(removed)
+    }
+
+    /// Signatures of the registered methods, including streaming and system methods, sorted
+    pub fn list_methods(&self) -> Result<Vec<String>, ErrorVariant> {
+        let mut signatures: Vec<String> = {
+            self.hm_methods
+                .read()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|hm| hm.keys().cloned().collect())?
+        };
+
+        {
+            self.streaming_methods
+                .read()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|hm| signatures.extend(hm.keys().cloned()))?;
+        }
+
+        signatures.sort();
+        signatures.dedup();
+        Ok(signatures)
+    }
+
+    /// Number of registered signatures, as listed by [list_methods](JrpcHandler::list_methods)
+    pub fn method_count(&self) -> Result<usize, ErrorVariant> {
+        self.list_methods().map(|signatures| signatures.len())
+    }
+
+    fn check_signature(signature: String) -> Result<String, ErrorVariant> {
+        // https://www.jsonrpc.org/specification#extensions
+        if signature.starts_with("rpc.") {
+            return Err(ErrorVariant::ReservedMethodSignature(signature));
+        }
+
+        Ok(signature)
+    }
+
+    fn insert_method<F: JrpcMethodTrait<'a> + Send + Sync + 'a>(
+        &self,
+        signature: String,
+        jrpc_method: F,
+    ) -> Result<&Self, ErrorVariant> {
+        self.insert_shared_method(signature, Arc::new(jrpc_method))
+    }
+
+    fn insert_shared_method(
+        &self,
+        signature: String,
+        jrpc_method: Arc<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>,
+    ) -> Result<&Self, ErrorVariant> {
+        let log_message = format!("Signature {} registered as method", &signature);
+
+        {
+            self.hm_methods
+                .write()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut hm| {
+                    hm.insert(signature, jrpc_method);
+                })?;
+        }
+
+        trace!("{}", log_message);
+        Ok(self)
+    }
+
+    /// Describe the method registered under `signature` in the
+    /// [OpenRPC document](JrpcHandler::to_openrpc_document)
+    ///
+    /// The schema is dropped when the method is unregistered.
+    #[cfg(feature = "openrpc")]
+    pub fn set_method_schema<T: ToString>(
+        &self,
+        signature: T,
+        schema: MethodSchema,
+    ) -> Result<&Self, ErrorVariant> {
+        {
+            self.schemas
+                .write()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|mut schemas| {
+                    schemas.insert(signature.to_string(), schema);
+                })?;
+        }
+
+        Ok(self)
+    }
+
+    /// OpenRPC document describing the registered methods, sorted by name
+    ///
+    /// Methods without a [schema](JrpcHandler::set_method_schema) are listed without params and
+    /// with a result accepting any value. System methods under the `rpc.` prefix are not listed.
+    /// The `info` object is a placeholder, meant to be replaced by the caller.
+    #[cfg(feature = "openrpc")]
+    pub fn to_openrpc_document(&self) -> Result<JsonValue, ErrorVariant> {
+        let signatures = self.list_methods()?;
+        let schemas = self
+            .schemas
+            .read()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)?;
+
+        let methods = signatures
+            .into_iter()
+            .filter(|signature| !signature.starts_with("rpc."))
+            .map(|signature| {
+                let schema = schemas.get(&signature).cloned().unwrap_or_default();
+                (signature, schema)
+            })
+            .collect();
+
+        Ok(openrpc::document(methods))
+    }
+
+    /// Immutable copy of the registered methods, dispatching without any locking
+    ///
+    /// Later registrations are not visible to the snapshot, so take a new one after changing the
+    /// method set.
+    pub fn snapshot(&self) -> Result<JrpcHandlerSnapshot<'a>, ErrorVariant> {
+        let methods = self
+            .hm_methods
+            .read()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+            .map(|hm| hm.clone())?;
+        let config = self.read_config(HandlerConfig::clone)?;
+
+        Ok(JrpcHandlerSnapshot {
+            methods: Arc::new(methods),
+            config: Arc::new(config),
+        })
+    }
+
+    /// Parse and dispatch a message
+    ///
+    /// Use [handle_str](JrpcHandler::handle_str) to handle a borrowed message without copying it.
+    pub fn handle_message<T: ToString>(
+        &self,
+        message: T,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let message = message.to_string();
+        self.handle_str(&message)
+    }
+
+    /// Parse and dispatch a message as [handle_message](JrpcHandler::handle_message), handing
+    /// `context` to the method, see
+    /// [generate_future_with_context](JrpcMethodTrait::generate_future_with_context)
+    ///
+    /// Meant for data attached by the transport, such as the address of the client.
+    pub fn handle_message_with_context<T: AsRef<str>, C: Any + Send + Sync>(
+        &self,
+        message: T,
+        context: C,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let message = match self.preprocess(message.as_ref())? {
+            Ok(m) => m,
+            Err(response) => return Ok(Box::new(future::ok(Some(response)))),
+        };
+
+        let request = self.parse_request(&message)?;
+        self.handle_request_with_context(request, JrpcContext::new(context))
+    }
+
+    /// Parse and dispatch a message, blocking the current thread until its future resolves
+    ///
+    /// Meant for tests and simple tools. Never call it from a task of an executor, which would be
+    /// blocked as well.
+    pub fn handle_message_blocking<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> Result<Option<JrpcResponse>, ErrorVariant> {
+        self.handle_str(message.as_ref())?.wait()
+    }
+
+    /// Parse and dispatch a borrowed message, without copying it
+    pub fn handle_str(
+        &self,
+        message: &str,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let message = match self.preprocess(message)? {
+            Ok(m) => m,
+            Err(response) => return Ok(Box::new(future::ok(Some(response)))),
+        };
+
+        let request = self.parse_request(&message)?;
+        trace!("Message {} generated request {:?}", &message, &request);
+
+        self.handle_request(request)
+    }
+
+    /// Parse and dispatch a message read from `reader`, streaming the params of the methods
+    /// registered with [register_streaming_method](JrpcHandler::register_streaming_method)
+    ///
+    /// The message is parsed on its own thread, so a streaming method runs while its params are
+    /// still being read. Only array params can be streamed, and `method` must precede `params` in
+    /// the message. Any other message is read in full and dispatched as with
+    /// [handle_message](JrpcHandler::handle_message). Batches are not supported.
+    ///
+    /// The remaining members of a streamed request are checked once the message is read, so an
+    /// invalid version or id fails the future even if the method already ran. The raw
+    /// preprocessor, timeouts and in-flight limits don't apply to streamed requests.
+    ///
+    /// A streaming method would run before its request is complete, so it can't be checked by the
+    /// [pre hooks](JrpcHandler::add_pre_hook). While any is registered, streamed requests fail
+    /// with `ErrorVariant::InvalidRequest` without running the method.
+    pub fn handle_reader<R: Read + Send + 'static>(
+        &self,
+        reader: R,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let streaming: HashSet<String> = {
+            self.streaming_methods
+                .read()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|hm| hm.keys().cloned().collect())?
+        };
+
+        let handler = self.clone();
+        Ok(Box::new(
+            streaming::parse(reader, streaming)
+                .and_then(move |head| match head {
+                    Head::Complete(members) => {
+                        let future = members.and_then(|members| {
+                            let message = JsonValue::Object(members).to_string();
+                            handler.handle_str(&message)
+                        });
+                        future::result(future).flatten()
+                    }
+                    Head::Streaming(method, params, tail) => {
+                        future::result(handler.handle_streamed(method, params, tail)).flatten()
+                    }
+                })
+                .map_err(ErrorVariant::into_cause),
+        ))
+    }
+
+    fn handle_streamed(
+        &self,
+        method: String,
+        params: ParamsStream,
+        tail: oneshot::Receiver<Result<streaming::Members, ErrorVariant>>,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        self.check_method_allowed(&method)?;
+
+        let hooked = self.read_config(|config| !config.pre_hooks.is_empty())?;
+
+        if hooked {
+            debug!("Streamed request to {} refused by the pre hooks", &method);
+            return Err(ErrorVariant::InvalidRequest(format!(
+                "Params of '{}' can't be streamed past the pre hooks",
+                method
+            )));
+        }
+
+        self.rate_limits.acquire(&method)?;
+
+        // The method may have been unregistered while its message was being parsed
+        let jrpc_method = {
+            self.streaming_methods
+                .read()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .and_then(|hm| {
+                    hm.get(&method)
+                        .cloned()
+                        .ok_or_else(|| ErrorVariant::MethodSignatureNotFound(method.clone()))
+                })?
+        };
+
+        let future = jrpc_method
+            .generate_future(params)
+            .map_err(|e| self.method_error(&method, e))?;
+
+        let handler = self.clone();
+        let tail = tail
+            .map_err(|_| ErrorVariant::InternalError)
+            .and_then(|members| members);
+        Ok(Box::new(
+            future
+                .map_err(move |e| handler.method_error(&method, e))
+                .join(tail)
+                .and_then(|(result, members)| streaming::respond(result, members)),
+        ))
+    }
+
+    /// Parse and dispatch a message that must be answered before `deadline`
+    ///
+    /// Past the deadline, the request is answered with the same `-32000` error as a
+    /// [timeout](JrpcHandler::set_method_timeout). A deadline that already passed is answered
+    /// right away, without dispatching the request.
+    pub fn handle_message_with_deadline<T: AsRef<str>>(
+        &self,
+        message: T,
+        deadline: Instant,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let message = match self.preprocess(message.as_ref())? {
+            Ok(m) => m,
+            Err(response) => return Ok(Box::new(future::ok(Some(response)))),
+        };
+
+        let request = self.parse_request(&message)?;
+        let method = request.get_method().clone();
+        let options = self.error_options()?;
+
+        let now = Instant::now();
+        let future = if deadline <= now {
+            warn!("Deadline of a request to '{}' already passed", &method);
+            let response = Some(request)
+                .filter(|request| !request.is_notification())
+                .map(|request| {
+                    let error =
+                        JrpcError::from(ErrorVariant::Timeout(method, Duration::from_secs(0)));
+                    JrpcResponse::error_with_request_id(error, &request)
+                });
+
+            Box::new(future::ok(response))
+        } else {
+            let future = self.handle_request(request.clone())?;
+            JrpcHandler::with_timeout(future, method, deadline - now, request)?
+        };
+
+        Ok(Box::new(future.map(move |response| {
+            response.map(|r| r.map_error(|e| options.decorate(e)))
+        })))
+    }
+
+    /// Parse and dispatch a message, answering client faults with error objects
+    ///
+    /// Unlike [handle_message](JrpcHandler::handle_message), protocol errors such as an unknown
+    /// method, and failures of the method itself, resolve to an error response, or to `None` for a
+    /// notification. Only internal faults of the server are returned as `Err`, so they can be
+    /// handled and logged without being sent to the client, as `DispatchError::Internal`. See
+    /// [DispatchError] for the classification.
+    pub fn dispatch<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> Result<
+        Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = DispatchError>>,
+        DispatchError,
+    > {
+        let message = match self.preprocess(message.as_ref())? {
+            Ok(m) => m,
+            Err(response) => return Ok(Box::new(future::ok(Some(response)))),
+        };
+
+        let options = self.error_options()?;
+        let request = match self.parse_request(&message) {
+            Ok(r) => r,
+            Err(e) => {
+                let response =
+                    JrpcHandler::protocol_error_response(e, JsonValue::Null, None, options)?;
+                return Ok(Box::new(future::ok(Some(response))));
+            }
+        };
+
+        let is_notification = request.is_notification();
+        let id = request.get_id().clone().unwrap_or(JsonValue::Null);
+        let namespace = self.error_namespace(request.get_method())?;
+        let respond = move |e: ErrorVariant| -> Result<_, DispatchError> {
+            let response = JrpcHandler::protocol_error_response(e, id, namespace, options)?;
+            Ok(Some(response).filter(|_| !is_notification))
+        };
+
+        match self.dispatch_request(request, JrpcContext::default()) {
+            Ok(f) => Ok(Box::new(f.or_else(respond))),
+            Err(e) => Ok(Box::new(future::result(respond(e)))),
+        }
+    }
+
+    /// Dispatch every message of `messages`, such as the lines of a newline-delimited connection,
+    /// resolving to their responses in the same order
+    ///
+    /// Messages are dispatched as by [dispatch](JrpcHandler::dispatch), so client faults are
+    /// answered with error responses, and notifications yield `None`. Blank messages are skipped.
+    pub fn serve_stream<S>(
+        &self,
+        messages: S,
+    ) -> Box<dyn 'a + Stream<Item = Option<JrpcResponse>, Error = ErrorVariant>>
+    where
+        S: 'a + Stream<Item = String, Error = ErrorVariant>,
+    {
+        let handler = self.clone();
+        Box::new(
+            messages
+                .filter(|message| !message.trim().is_empty())
+                .and_then(move |message| {
+                    future::result(handler.dispatch(message))
+                        .flatten()
+                        .map_err(DispatchError::into_inner)
+                }),
+        )
+    }
+
+    /// Parse and dispatch a message, answering every failure with an error response
+    ///
+    /// Unlike [dispatch](JrpcHandler::dispatch), internal faults are answered as well, with a
+    /// `-32603` error, so the future never fails. When the message is not a valid request, the
+    /// error carries its id if one can be read from it, or `null` otherwise. Notifications still
+    /// resolve to `None`.
+    pub fn handle_message_to_response<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>> {
+        let options = self.error_options().unwrap_or_default();
+        let respond = move |e: ErrorVariant, id: JsonValue, namespace: Option<i32>| {
+            JrpcHandler::dispatch_error_response(e, id, namespace, options)
+        };
+
+        let message = match self.preprocess(message.as_ref()) {
+            Ok(Ok(m)) => m,
+            Ok(Err(response)) => return Box::new(future::ok(Some(response))),
+            Err(e) => return Box::new(future::ok(Some(respond(e, JsonValue::Null, None)))),
+        };
+
+        let request = match self.parse_request(&message) {
+            Ok(r) => r,
+            Err(e) => {
+                let id = JrpcHandler::peek_id(&message);
+                return Box::new(future::ok(Some(respond(e, id, None))));
+            }
+        };
+
+        let is_notification = request.is_notification();
+        let id = request.get_id().clone().unwrap_or(JsonValue::Null);
+        let namespace = self.error_namespace(request.get_method()).unwrap_or(None);
+        let respond = move |e: ErrorVariant| {
+            let response = respond(e, id, namespace);
+            Ok(Some(response).filter(|_| !is_notification))
+        };
+
+        match self.dispatch_request(request, JrpcContext::default()) {
+            Ok(f) => Box::new(f.or_else(respond)),
+            Err(e) => Box::new(future::result(respond(e))),
+        }
+    }
+
+    /// Id of a message that failed to parse as a request, if it has a valid one
+    fn peek_id(message: &str) -> JsonValue {
+        match serde_json::from_str::<JsonValue>(message) {
+            Ok(JsonValue::Object(mut message)) => match message.remove("id") {
+                Some(id @ JsonValue::String(_)) | Some(id @ JsonValue::Number(_)) => id,
+                _ => JsonValue::Null,
+            },
+            _ => JsonValue::Null,
+        }
+    }
+
+    pub(crate) fn parse_request(&self, message: &str) -> Result<JrpcRequest, ErrorVariant> {
+        self.read_config(|config| config.parse_request(message))?
+    }
+
+    /// Apply the raw preprocessor, if any. A failure is returned as the response to send back
+    pub(crate) fn preprocess<'m>(
+        &self,
+        message: &'m str,
+    ) -> Result<Result<Cow<'m, str>, JrpcResponse>, ErrorVariant> {
+        let preprocessed = self
+            .read_config(|config| config.raw_preprocessor.clone())?
+            .map(|p| p(message.to_string()));
+
+        match preprocessed {
+            None => Ok(Ok(Cow::Borrowed(message))),
+            Some(Ok(m)) => Ok(Ok(Cow::Owned(m))),
+            Some(Err(e)) => JrpcResponse::new(None, Some(e), JsonValue::Null).map(Err),
+        }
+    }
+
+    /// Dispatch an already parsed request to its registered method
+    pub fn handle_request(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        self.handle_request_with_context(request, JrpcContext::default())
+    }
+
+    /// Dispatch an already parsed request to its registered method, handing it `context`, see
+    /// [generate_future_with_context](JrpcMethodTrait::generate_future_with_context)
+    ///
+    /// Failures of the method are returned as the method raised them.
+    pub fn handle_request_with_context(
+        &self,
+        request: JrpcRequest,
+        context: JrpcContext,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let future = self
+            .dispatch_request(request, context)
+            .map_err(ErrorVariant::into_cause)?;
+
+        Ok(Box::new(future.map_err(ErrorVariant::into_cause)))
+    }
+
+    /// Dispatch `request` as [handle_request_with_context](JrpcHandler::handle_request_with_context),
+    /// with the failures of the method wrapped in `ErrorVariant::MethodError` so they can be told
+    /// apart from the faults of the handler
+    fn dispatch_request(
+        &self,
+        mut request: JrpcRequest,
+        context: JrpcContext,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let method = request.get_method().clone();
+
+        self.check_method_allowed(&method)?;
+
+        let (pre_hooks, observed) = self.read_config(|config| {
+            let observed = Some(request.clone()).filter(|_| !config.post_hooks.is_empty());
+            (config.pre_hooks.clone(), observed)
+        })?;
+
+        let rejected = pre_hooks.iter().try_for_each(|hook| hook(&request));
+
+        if let Err(error) = rejected {
+            debug!("Request to {} rejected by a pre hook", &method);
+            let response = Some(JrpcResponse::error_with_request_id(error, &request))
+                .filter(|_| !request.is_notification());
+            return Ok(self.with_post_hooks(observed, Box::new(future::ok(response))));
+        }
+
+        self.rate_limits.acquire(&method)?;
+
+        // The lock is released before generating the future, so methods may use the handler
+        let attach = self.read_config(|config| config.attach_signatures)?;
+        let found = {
+            self.hm_methods
+                .read()
+                .map_err(|_| ErrorVariant::RwLockPoisoned)
+                .map(|hm| {
+                    hm.get_key_value(&method).map(|(signature, jrpc_method)| {
+                        (
+                            Some(signature).filter(|_| attach).cloned(),
+                            jrpc_method.clone(),
+                        )
+                    })
+                })?
+        };
+
+        let jrpc_method = match found {
+            Some((signature, jrpc_method)) => {
+                if let Some(signature) = signature {
+                    request.set_matched_signature(signature);
+                }
+                jrpc_method
+            }
+            None => self
+                .read_config(|config| config.default_method.clone())?
+                .ok_or_else(|| ErrorVariant::MethodSignatureNotFound(method.clone()))?,
+        };
+
+        if request.is_notification() {
+            if let Some(hook) = self.read_config(|config| config.notification_hook.clone())? {
+                hook(&request);
+            }
+        }
+
+        let mismatch = self.read_config(|config| {
+            config
+                .params_specs
+                .get(&method)
+                .map(|spec| spec.check(request.get_params().as_ref()))
+        })?;
+
+        if let Some(Err(e)) = mismatch {
+            debug!("Request to {} rejected by its params spec: {}", &method, e);
+            let response = Some(JrpcResponse::error_with_request_id(
+                JrpcError::from(e),
+                &request,
+            ))
+            .filter(|_| !request.is_notification());
+            return Ok(self.with_post_hooks(observed, Box::new(future::ok(response))));
+        }
+
+        let priority = self.method_priority(&method)?;
+
+        let future: Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>> =
+            match InFlight::admit(&self.inflight, priority)? {
+                Admission::Admitted(guard) => {
+                    self.dispatch_admitted(jrpc_method, request, context, guard)?
+                }
+                Admission::Queued(queued) => {
+                    trace!("Request to {} queued with priority {}", &method, priority);
+
+                    let handler = self.clone();
+                    Box::new(queued.and_then(move |guard| {
+                        let future =
+                            handler.dispatch_admitted(jrpc_method, request, context, guard);
+                        future::result(future).flatten()
+                    }))
+                }
+            };
+
+        Ok(self.with_post_hooks(observed, future))
+    }
+
+    /// Call the post hooks with `request` and the response `future` resolves to, if any
+    fn with_post_hooks(
+        &self,
+        request: Option<JrpcRequest>,
+        future: Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
+    ) -> Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>> {
+        let request = match request {
+            Some(request) => request,
+            None => return future,
+        };
+
+        let handler = self.clone();
+        Box::new(future.map(move |response| {
+            let hooks = handler.read_config(|config| config.post_hooks.clone());
+            if let (Some(response), Ok(hooks)) = (&response, hooks) {
+                hooks.iter().for_each(|hook| hook(&request, response));
+            }
+            response
+        }))
+    }
+
+    /// Generate the future of a request granted an in-flight slot, held until it resolves
+    fn dispatch_admitted(
+        &self,
+        jrpc_method: Arc<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>,
+        request: JrpcRequest,
+        context: JrpcContext,
+        guard: InFlightGuard,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let method = request.get_method().clone();
+        let id = request.get_id().clone().unwrap_or(JsonValue::Null);
+        let is_notification = request.is_notification();
+        let (timeout, catch, strict) = self.read_config(|config| {
+            (
+                config.timeouts.get(&method),
+                config.catching_methods.contains(&method),
+                config.strict_mode,
+            )
+        })?;
+        let timeout = timeout.map(|timeout| (timeout, request.clone()));
+
+        // Failures to generate the future of a catching method are answered like the others
+        let future = match jrpc_method.generate_future_with_context(request, context) {
+            Ok(future) => future,
+            Err(e) if catch => Box::new(future::err(e)),
+            Err(e) => return Err(self.method_error(&method, e)),
+        };
+
+        let handler = self.clone();
+        let caught_id = id.clone();
+        let namespace = self.error_namespace(&method)?;
+        let timed_method = method.clone();
+        let future: Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>> =
+            Box::new(
+                future
+                    .map_err(move |e| handler.method_error(&method, e))
+                    .and_then(move |response| JrpcHandler::check_response_id(strict, id, response))
+                    .or_else(move |e| match catch {
+                        true => {
+                            JrpcResponse::new(None, Some(JrpcError::from(e)), caught_id).map(Some)
+                        }
+                        false => Err(e),
+                    })
+                    .map(move |response| {
+                        response.map(|r| r.map_error(|e| JrpcHandler::remap_error(namespace, e)))
+                    }),
+            );
+
+        let future = match timeout {
+            Some((timeout, request)) => {
+                JrpcHandler::with_timeout(future, timed_method, timeout, request)?
+            }
+            None => future,
+        };
+
+        // Notifications are never answered, whatever their method resolved to
+        let options = self.error_options()?;
+        Ok(Box::new(future.then(move |result| {
+            drop(guard);
+            result.map(|response| {
+                response
+                    .filter(|_| !is_notification)
+                    .map(|r| r.map_error(|e| options.decorate(e)))
+            })
+        })))
+    }
+
+    /// Answer `request` with a timeout error if `future` doesn't resolve within `timeout`
+    fn with_timeout(
+        future: Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
+        method: String,
+        timeout: Duration,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        Ok(Box::new(future.select2(timeout::deadline(timeout)?).then(
+            move |result| match result {
+                Ok(Either::A((response, _))) => Ok(response),
+                Err(Either::A((e, _))) => Err(e),
+                Err(Either::B((e, _))) => Err(e),
+                Ok(Either::B(_)) => {
+                    warn!(
+                        "Method '{}' timed out after {}ms",
+                        method,
+                        timeout.as_millis()
+                    );
+
+                    if request.is_notification() {
+                        return Ok(None);
+                    }
+
+                    let error = JrpcError::from(ErrorVariant::Timeout(method, timeout));
+                    Ok(Some(JrpcResponse::error_with_request_id(error, &request)))
+                }
+            },
+        )))
+    }
+
+    fn check_response_id(
+        strict: bool,
+        id: JsonValue,
+        response: Option<JrpcResponse>,
+    ) -> Result<Option<JrpcResponse>, ErrorVariant> {
+        match &response {
+            Some(r) if r.get_id() != &id => {
+                warn!(
+                    "Response id {} doesn't match request id {}",
+                    r.get_id(),
+                    &id
+                );
+                if strict {
+                    return Err(ErrorVariant::IdMismatch(id, r.get_id().clone()));
+                }
+            }
+            _ => (),
+        }
+
+        Ok(response)
+    }
+
+    /// Handle a message that may either be a single request or a batch of requests
+    ///
+    /// Protocol errors and method failures are reported as error responses instead of `Err`, as
+    /// required for batches, while internal faults of the server fail the whole future, as with
+    /// [dispatch](JrpcHandler::dispatch). The future resolves to `None` when there is nothing to
+    /// reply, e.g. a batch of notifications.
+    pub fn handle_batch_message<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> Result<
+        Box<dyn 'a + Future<Item = Option<JrpcBatchOutcome>, Error = ErrorVariant>>,
+        ErrorVariant,
+    > {
+        self.handle_batch(message.as_ref(), None, None)
+    }
+
+    /// Handle a message as [handle_batch_message](JrpcHandler::handle_batch_message), running at
+    /// most `max_concurrent` requests of a batch at once
+    ///
+    /// The other requests of the batch wait for a running one to resolve before being dispatched.
+    /// A limit of `0` is treated as `1`.
+    pub fn handle_batch_message_with_concurrency<T: AsRef<str>>(
+        &self,
+        message: T,
+        max_concurrent: usize,
+    ) -> Result<
+        Box<dyn 'a + Future<Item = Option<JrpcBatchOutcome>, Error = ErrorVariant>>,
+        ErrorVariant,
+    > {
+        self.handle_batch(message.as_ref(), Some(max_concurrent.max(1)), None)
+    }
+
+    /// Dispatch a single or batch message, checking the ids of its requests against `in_flight`
+    pub(crate) fn handle_batch(
+        &self,
+        message: &str,
+        max_concurrent: Option<usize>,
+        in_flight: Option<InFlightIds>,
+    ) -> Result<
+        Box<dyn 'a + Future<Item = Option<JrpcBatchOutcome>, Error = ErrorVariant>>,
+        ErrorVariant,
+    > {
+        let message = match self.preprocess(message)? {
+            Ok(m) => m,
+            Err(response) => {
+                return Ok(Box::new(future::ok(Some(JrpcBatchOutcome::Single(
+                    response,
+                )))))
+            }
+        };
+        trace!("Batch message {}", &message);
+
+        let value = match serde_json::from_str::<JsonValue>(&message) {
+            Ok(v) => v,
+            Err(e) => {
+                let response =
+                    JrpcHandler::error_response(ErrorVariant::JsonParseError(e), JsonValue::Null);
+                return Ok(Box::new(future::ok(Some(JrpcBatchOutcome::Single(
+                    response,
+                )))));
+            }
+        };
+
+        let preserve_raw_ids = self.read_config(|config| config.preserve_raw_ids)?;
+        let elements = match value {
+            JsonValue::Array(elements) => elements,
+            v => {
+                let raw = Some(message.to_string()).filter(|_| preserve_raw_ids);
+                let future = self
+                    .handle_value(v, raw, in_flight.as_ref())
+                    .map(|response| response.map(JrpcBatchOutcome::Single));
+                return Ok(Box::new(future));
+            }
+        };
+
+        if elements.is_empty() {
+            let response = JrpcResponse::new(
+                None,
+                Some(JrpcError::from(JrpcErrorEnum::InvalidRequest)),
+                JsonValue::Null,
+            )?;
+            return Ok(Box::new(future::ok(Some(JrpcBatchOutcome::Single(
+                response,
+            )))));
+        }
+
+        // The text of every element, to read the ids as written
+        let raw_elements = if preserve_raw_ids {
+            serde_json::from_str::<Vec<&JsonRawValue>>(&message)?
+                .into_iter()
+                .map(|raw| Some(raw.get().to_string()))
+                .collect()
+        } else {
+            vec![None; elements.len()]
+        };
+        let elements = elements.into_iter().zip(raw_elements);
+
+        let responses: Box<
+            dyn 'a + Future<Item = Vec<Option<JrpcResponse>>, Error = ErrorVariant>,
+        > = match max_concurrent {
+            Some(max) => {
+                // Requests are only dispatched once the buffer has room for them
+                let handler = self.clone();
+                Box::new(
+                    stream::iter_ok(elements)
+                        .map(move |(v, raw)| handler.handle_value(v, raw, in_flight.as_ref()))
+                        .buffered(max)
+                        .collect(),
+                )
+            }
+            None => {
+                let futures: Vec<_> = elements
+                    .map(|(v, raw)| self.handle_value(v, raw, in_flight.as_ref()))
+                    .collect();
+                Box::new(future::join_all(futures))
+            }
+        };
+
+        let future = responses.and_then(|responses| {
+            let mut builder = BatchResponseBuilder::new(responses.len(), BatchOrder::Request);
+            for (index, response) in responses.into_iter().enumerate() {
+                builder.push(index, response)?;
+            }
+
+            let responses = builder.build()?;
+            if responses.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(JrpcBatchOutcome::Batch(JrpcBatchResponse::new(
+                    responses,
+                ))))
+            }
+        });
+
+        Ok(Box::new(future))
+    }
+
+    /// Dispatch a single JSON value, parsed from `raw` when raw ids are preserved, converting
+    /// protocol errors and method failures into error responses. Internal faults fail the future
+    fn handle_value(
+        &self,
+        value: JsonValue,
+        raw: Option<String>,
+        in_flight: Option<&InFlightIds>,
+    ) -> Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>> {
+        // The value is valid JSON, so any failure here means it is not a valid request object
+        let lenient = match self.read_config(|config| config.lenient_version) {
+            Ok(lenient) => lenient,
+            Err(e) => return Box::new(future::err(e)),
+        };
+        let request = JrpcRequest::from_value_with(value, raw.as_deref(), lenient);
+
+        let request = match request {
+            Ok(r) => r,
+            Err(_) => {
+                let error = JrpcError::from(JrpcErrorEnum::InvalidRequest);
+                let error = self.error_options().unwrap_or_default().decorate(error);
+                let response = JrpcResponse::new(None, Some(error), JsonValue::Null);
+                return Box::new(future::result(response.map(Some)));
+            }
+        };
+
+        let is_notification = request.is_notification();
+        let id = request.get_id().clone().unwrap_or(JsonValue::Null);
+        let namespace = self.error_namespace(request.get_method()).unwrap_or(None);
+        let options = self.error_options().unwrap_or_default();
+
+        let respond = move |e: ErrorVariant| {
+            JrpcHandler::protocol_error_response(e, id, namespace, options).map(Some)
+        };
+
+        let guard = match in_flight.map(|in_flight| in_flight.track(&request)) {
+            Some(Ok(guard)) => guard,
+            Some(Err(e)) => return Box::new(future::result(respond(e))),
+            None => None,
+        };
+
+        let future: Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>> =
+            match self.dispatch_request(request, JrpcContext::default()) {
+                Ok(f) => Box::new(f.or_else(respond).then(move |result| {
+                    drop(guard);
+                    result
+                })),
+                Err(e) => Box::new(future::result(respond(e))),
+            };
+
+        if is_notification {
+            Box::new(future.map(|_| None))
+        } else {
+            future
+        }
+    }
+
+    fn error_response(error: ErrorVariant, id: JsonValue) -> JrpcResponse {
+        let error = JrpcError::from(error);
+        JrpcResponse::new(None, Some(error), id)
+            .expect("An error response with a valid id is always valid")
+    }
+
+    /// Error response for a failed dispatch, remapping the errors raised by the method itself
+    fn dispatch_error_response(
+        error: ErrorVariant,
+        id: JsonValue,
+        namespace: Option<i32>,
+        options: ErrorOptions,
+    ) -> JrpcResponse {
+        let from_method = error.get_method().is_some();
+        let description = error.to_string();
+        let mut response = JrpcHandler::error_response(error, id);
+
+        if let Some(e) = response.get_error() {
+            log!(
+                e.log_level(),
+                "Dispatch failed with {}: {}",
+                e.get_code(),
+                description
+            );
+        }
+
+        let internal = response
+            .get_error()
+            .as_ref()
+            .map(|e| e.get_code() == &i32::from(JrpcErrorEnum::InternalError))
+            .unwrap_or(false);
+        if options.backtrace && internal && !from_method {
+            response = response.map_error(JrpcError::with_backtrace);
+        }
+
+        if from_method {
+            response = response.map_error(|e| JrpcHandler::remap_error(namespace, e));
+        }
+
+        response.map_error(|e| options.decorate(e))
+    }
+
+    /// Error response for a protocol error or a method failure, or the error itself for an
+    /// internal fault
+    fn protocol_error_response(
+        error: ErrorVariant,
+        id: JsonValue,
+        namespace: Option<i32>,
+        options: ErrorOptions,
+    ) -> Result<JrpcResponse, ErrorVariant> {
+        if error.get_method().is_none() && !error.is_protocol() {
+            return Err(error);
+        }
+
+        Ok(JrpcHandler::dispatch_error_response(
+            error, id, namespace, options,
+        ))
+    }
+
+    fn check_method_allowed(&self, method: &str) -> Result<(), ErrorVariant> {
+        let allowed = self
+            .read_config(|config| config.method_filter.clone())?
+            .map(|f| f(method))
+            .unwrap_or(true);
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(ErrorVariant::MethodDisabled(method.to_string()))
+        }
+    }
+
+    fn method_priority(&self, method: &str) -> Result<i32, ErrorVariant> {
+        self.read_config(|config| config.priorities.get(method).cloned().unwrap_or(0))
+    }
+
+    fn error_options(&self) -> Result<ErrorOptions, ErrorVariant> {
+        self.read_config(|config| ErrorOptions {
+            backtrace: config.capture_backtraces,
+            categories: config.error_categories,
+        })
+    }
+
+    /// Base of the longest error namespace prefix matching `method`
+    fn error_namespace(&self, method: &str) -> Result<Option<i32>, ErrorVariant> {
+        self.read_config(|config| {
+            config
+                .error_namespaces
+                .iter()
+                .filter(|(prefix, _)| method.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, base)| *base)
+        })
+    }
+
+    fn remap_error(namespace: Option<i32>, error: JrpcError) -> JrpcError {
+        let code = *error.get_code();
+
+        match namespace {
+            Some(base) if (-32768..=-32000).contains(&code) => JrpcError::new(
+                base.saturating_add(-32000 - code),
+                error.get_message(),
+                error.get_data().clone(),
+            ),
+            _ => error,
+        }
+    }
+
+    fn method_error(&self, method: &str, mut error: ErrorVariant) -> ErrorVariant {
+        if error.get_cause().is_protocol() {
+            return ErrorVariant::MethodError(method.to_string(), Box::new(error));
+        }
+
+        if let Ok(Some(hook)) = self.read_config(|config| config.internal_error_hook.clone()) {
+            hook(method, &error);
+        }
+
+        let capture = self.read_config(|config| config.capture_backtraces);
+        if cfg!(debug_assertions) && capture.unwrap_or(false) {
+            let backtrace = Backtrace::force_capture().to_string();
+            error!("Method '{}' failed: {}\n{}", method, error, backtrace);
+            error = ErrorVariant::WithBacktrace(Box::new(error), backtrace);
+        }
+
+        ErrorVariant::MethodError(method.to_string(), Box::new(error))
+    }
+}
+
+/// Read-only view of the methods of a handler, as returned by
+/// [snapshot](JrpcHandler::snapshot)
+///
+/// Meant for servers registering their methods once at startup. Messages are parsed as by the
+/// handler, following its [lenient version](JrpcHandler::set_lenient_version) and
+/// [raw id](JrpcHandler::set_preserve_raw_ids) settings. The method set, the
+/// [default method](JrpcHandler::set_default_method), the [pre hooks](JrpcHandler::add_pre_hook),
+/// the [params specs](JrpcHandler::register_method_with_schema), the
+/// [timeouts](JrpcHandler::set_default_timeout) and whether
+/// [signatures are attached](JrpcHandler::set_attach_signatures) apply as well, and notifications
+/// are never answered.
+///
+/// Everything else is skipped: the raw preprocessor, the filter, rate limits, in-flight limits,
+/// namespaces, post hooks, the notification hook, the internal error hook, error catching, strict
+/// response ids and error options. Dispatches are not tracked by
+/// [await_idle](JrpcHandler::await_idle).
+#[derive(Clone)]
+pub struct JrpcHandlerSnapshot<'a> {
+    methods: Arc<MethodMap<'a>>,
+    config: Arc<HandlerConfig<'a>>,
+}
+
+impl<'a> JrpcHandlerSnapshot<'a> {
+    /// Parse and dispatch a message, see [handle_message](JrpcHandler::handle_message)
+    pub fn handle_message<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let request = self.config.parse_request(message.as_ref())?;
+        self.handle_request(request)
+    }
+
+    /// Dispatch an already parsed request to its captured method, or to the default method
+    ///
+    /// Failures of the method are returned as the method raised them.
+    pub fn handle_request(
+        &self,
+        mut request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let method = request.get_method().clone();
+
+        if let Err(error) = self
+            .config
+            .pre_hooks
+            .iter()
+            .try_for_each(|hook| hook(&request))
+        {
+            debug!("Request to {} rejected by a pre hook", &method);
+            let response = Some(JrpcResponse::error_with_request_id(error, &request))
+                .filter(|_| !request.is_notification());
+            return Ok(Box::new(future::ok(response)));
+        }
+
+        let jrpc_method = match self.methods.get_key_value(&method) {
+            Some((signature, jrpc_method)) => {
+                if self.config.attach_signatures {
+                    request.set_matched_signature(signature.clone());
+                }
+                jrpc_method
+            }
+            None => self
+                .config
+                .default_method
+                .as_ref()
+                .ok_or_else(|| ErrorVariant::MethodSignatureNotFound(method.clone()))?,
+        };
+
+        let is_notification = request.is_notification();
+        if let Some(Err(e)) = self
+            .config
+            .params_specs
+            .get(&method)
+            .map(|spec| spec.check(request.get_params().as_ref()))
+        {
+            debug!("Request to {} rejected by its params spec: {}", &method, e);
+            let response = Some(JrpcResponse::error_with_request_id(
+                JrpcError::from(e),
+                &request,
+            ))
+            .filter(|_| !is_notification);
+            return Ok(Box::new(future::ok(response)));
+        }
+
+        let timeout = self.config.timeouts.get(&method);
+        let future = match timeout {
+            Some(timeout) => {
+                let future = jrpc_method.generate_future(request.clone())?;
+                JrpcHandler::with_timeout(future, method, timeout, request)?
+            }
+            None => jrpc_method.generate_future(request)?,
+        };
+
+        Ok(Box::new(future.map(move |response| {
+            response.filter(|_| !is_notification)
+        })))
+    }
+
+    /// Whether a method was registered under `signature` when the snapshot was taken
+    pub fn contains_method(&self, signature: &str) -> bool {
+        self.methods.contains_key(signature)
+    }
+}
+
+/// Optional additions to the errors answered by the handler
+#[derive(Debug, Clone, Copy, Default)]
+struct ErrorOptions {
+    backtrace: bool,
+    categories: bool,
+}
+
+impl ErrorOptions {
+    fn decorate(self, error: JrpcError) -> JrpcError {
+        if self.categories {
+            error.with_category()
+        } else {
+            error
+        }
+    }
+}
+
+/// Restore the registration replaced by [with_temporary_method](JrpcHandler::with_temporary_method)
+struct TemporaryMethod<'h, 'a> {
+    handler: &'h JrpcHandler<'a>,
+    signature: String,
+    previous: Option<Arc<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>>,
+}
+
+impl<'h, 'a> Drop for TemporaryMethod<'h, 'a> {
+    fn drop(&mut self) {
+        // Restoring must not panic, possibly while unwinding, so a poisoned map is still updated
+        let mut hm = self
+            .handler
+            .hm_methods
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+
+        match self.previous.take() {
+            Some(previous) => hm.insert(self.signature.clone(), previous),
+            None => hm.remove(&self.signature),
+        };
+
+        trace!("Signature {} restored", &self.signature);
+    }
+}
+
+/// Collect `(signature, method)` pairs into a handler
+///
+/// # Panics
+///
+/// Panics if a signature can't be registered. Use [try_from_iter](JrpcHandler::try_from_iter) to
+/// handle the failure instead.
+impl<'a, T: ToString> FromIterator<(T, Box<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>)>
+    for JrpcHandler<'a>
+{
+    fn from_iter<I: IntoIterator<Item = (T, Box<dyn JrpcMethodTrait<'a> + Send + Sync + 'a>)>>(
+        iter: I,
+    ) -> Self {
+        JrpcHandler::try_from_iter(iter)
+            .unwrap_or_else(|e| panic!("Failed to build the handler: {}", e))
     }
 }
@@ -0,0 +1,221 @@
+use crate::futures::prelude::*;
+use crate::futures::sync::oneshot;
+use crate::futures::task::{self, Task};
+use crate::ErrorVariant;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+
+/// Count of the dispatched futures that didn't resolve yet, with the dispatches waiting for a
+/// slot when the count is bounded
+pub(crate) struct InFlight {
+    state: Mutex<InFlightState>,
+}
+
+struct InFlightState {
+    count: usize,
+    max: Option<usize>,
+    queue: BinaryHeap<Waiter>,
+    next_seq: u64,
+    idle_tasks: Vec<Task>,
+}
+
+/// Dispatch waiting for a slot, granted by sending on `sender`
+struct Waiter {
+    priority: i32,
+    seq: u64,
+    sender: oneshot::Sender<()>,
+}
+
+// Ordered so the highest priority is at the top of the heap, first come first served among equals
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl InFlight {
+    pub(crate) fn new() -> Self {
+        let state = InFlightState {
+            count: 0,
+            max: None,
+            queue: BinaryHeap::new(),
+            next_seq: 0,
+            idle_tasks: vec![],
+        };
+        InFlight {
+            state: Mutex::new(state),
+        }
+    }
+
+    pub(crate) fn count(&self) -> Result<usize, ErrorVariant> {
+        self.state
+            .lock()
+            .map(|state| state.count)
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+    }
+
+    pub(crate) fn set_max(&self, max: Option<usize>) -> Result<(), ErrorVariant> {
+        self.state
+            .lock()
+            .map(|mut state| state.max = max)
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+    }
+
+    /// Register a new dispatch if there is a free slot, or queue it by `priority` otherwise. The
+    /// slot is released when the guard is dropped
+    pub(crate) fn admit(
+        inflight: &Arc<InFlight>,
+        priority: i32,
+    ) -> Result<Admission, ErrorVariant> {
+        let mut state = inflight
+            .state
+            .lock()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)?;
+
+        if state.max.map(|max| state.count < max).unwrap_or(true) {
+            state.count += 1;
+            return Ok(Admission::Admitted(InFlightGuard {
+                inflight: inflight.clone(),
+            }));
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.queue.push(Waiter {
+            priority,
+            seq,
+            sender,
+        });
+
+        Ok(Admission::Queued(Queued {
+            inflight: inflight.clone(),
+            receiver: Some(receiver),
+        }))
+    }
+
+    /// Hand the slot of a finished dispatch to the first queued one, or free it
+    fn release(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            while let Some(waiter) = state.queue.pop() {
+                // Fails if the queued dispatch was dropped
+                if waiter.sender.send(()).is_ok() {
+                    return;
+                }
+            }
+
+            state.count -= 1;
+            if state.count == 0 {
+                state.idle_tasks.drain(..).for_each(|t| t.notify());
+            }
+        }
+    }
+}
+
+pub(crate) struct InFlightGuard {
+    inflight: Arc<InFlight>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.inflight.release();
+    }
+}
+
+/// Outcome of [admit](InFlight::admit)
+pub(crate) enum Admission {
+    Admitted(InFlightGuard),
+    Queued(Queued),
+}
+
+/// Future resolving once a queued dispatch is granted a slot
+pub(crate) struct Queued {
+    inflight: Arc<InFlight>,
+    receiver: Option<oneshot::Receiver<()>>,
+}
+
+impl Future for Queued {
+    type Item = InFlightGuard;
+    type Error = ErrorVariant;
+
+    fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+        let granted = match self.receiver.as_mut() {
+            Some(receiver) => receiver.poll().map_err(|_| ErrorVariant::InternalError)?,
+            None => return Err(ErrorVariant::InternalError),
+        };
+
+        match granted {
+            Async::Ready(()) => {
+                self.receiver = None;
+                Ok(Async::Ready(InFlightGuard {
+                    inflight: self.inflight.clone(),
+                }))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        // A slot granted to a dispatch dropped before using it is passed on
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if let Ok(Some(())) = receiver.try_recv() {
+                self.inflight.release();
+            }
+        }
+    }
+}
+
+/// Future resolving once there are no dispatches in flight, as returned by
+/// [await_idle](crate::handler::JrpcHandler::await_idle)
+pub struct Idle {
+    inflight: Arc<InFlight>,
+}
+
+impl Idle {
+    pub(crate) fn new(inflight: Arc<InFlight>) -> Self {
+        Idle { inflight }
+    }
+}
+
+impl Future for Idle {
+    type Item = ();
+    type Error = ErrorVariant;
+
+    fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+        let mut state = self
+            .inflight
+            .state
+            .lock()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)?;
+
+        if state.count == 0 {
+            return Ok(Async::Ready(()));
+        }
+
+        // Polled again by the same task, e.g. within a `select`, which is already notified
+        if !state.idle_tasks.iter().any(Task::will_notify_current) {
+            state.idle_tasks.push(task::current());
+        }
+        Ok(Async::NotReady)
+    }
+}
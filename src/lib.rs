@@ -59,7 +59,7 @@
 //!         .register_method("some/copyParams", CopyParams::new().unwrap())
 //!
 //!         .and_then(|h| {
-//!             // `handle_message` will receive a raw implementation of `ToString` and return the
+//!             // `handle_message` will receive a raw implementation of `AsRef<str>` and return the
 //!             // associated future. If no future is found, an instance of
 //!             // `Err(ErrorVariant::MethodSignatureNotFound(String))` is returned
 //!             h.handle_message(
@@ -95,6 +95,33 @@
 //! }
 //! ```
 //!
+//! ## Async methods
+//!
+//! With the `derive` feature, `#[jrpc_method]` turns an `async fn` into a method. Its single argument
+//! receives the deserialized params, and the `Ok` value is serialized as the result.
+//!
+//! ```ignore
+//! use futures_jsonrpc::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct SubtractParams {
+//!     minuend: i64,
+//!     subtrahend: i64,
+//! }
+//!
+//! #[jrpc_method]
+//! async fn subtract(params: SubtractParams) -> Result<i64, JrpcError> {
+//!     Ok(params.minuend - params.subtrahend)
+//! }
+//!
+//! let handler = JrpcHandler::new().unwrap();
+//! handler.register_method("math/subtract", subtract_method()).unwrap();
+//! ```
+//!
+//! The `compat` feature alone provides `compat::method_future`, to reply with any
+//! `std` future from a hand-written `JrpcMethodTrait`.
+//!
 //! ## Detailed explanation
 //!
 //! ```
@@ -187,13 +214,14 @@
 //! impl<'r> JrpcMethodTrait<'r> for CopyParams<'r> {
 //!     // `generate_future` can generate any `Future` that respects the trait signature. This can be a
 //!     // foreign structure, or just a copy of `self`, in case it implements `Future`. This can also
-//!     // be a decision based on the received `JrpcRequest`.
+//!     // be a decision based on the received `JrpcRequest`, which `register_dispatched_method`
+//!     // offers for methods with several forms.
 //!     //
 //!     // Since its not a reference, there are no restrictions.
 //!     fn generate_future(
 //!         &self,
 //!         request: JrpcRequest,
-//!     ) -> Result<Box<'r + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+//!     ) -> Result<Box<dyn 'r + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
 //!     {
 //!         Ok(Box::new(self.clone_with_request(request)?))
 //!     }
@@ -203,40 +231,290 @@
 #[macro_use]
 extern crate log;
 
-pub use crate::handler::JrpcHandler;
-pub use crate::method::JrpcMethodTrait;
-pub use crate::parser::{JrpcError, JrpcErrorEnum, JrpcRequest, JrpcResponse, JrpcResponseParam};
+#[cfg(feature = "derive")]
+pub use futures_jsonrpc_derive::jrpc_method;
+
+pub use crate::client::{IdGenerator, JrpcClient, PendingCalls};
+pub use crate::handler::{JrpcHandler, JrpcHandlerSnapshot};
+#[cfg(feature = "lint")]
+pub use crate::lint::{lint_request, lint_response, LintIssue};
+pub use crate::method::{
+    JrpcContext, JrpcMethodTrait, JrpcNotificationMethodTrait, JrpcOutcomeMethodTrait,
+    JrpcStreamingMethodTrait, MethodOutcome, ParamsStream,
+};
+pub use crate::parser::{
+    BatchOrder, BatchResponseBuilder, JrpcBatchOutcome, JrpcBatchRequest, JrpcBatchResponse,
+    JrpcError, JrpcErrorEnum, JrpcLazyResponse, JrpcMessage, JrpcRequest, JrpcRequestBuilder,
+    JrpcResponse, JrpcResponseParam, TryFromJrpcError,
+};
+pub use crate::session::JrpcSession;
 pub use futures;
 pub use serde_json::error::Error as JsonError;
+pub use serde_json::value::RawValue as JsonRawValue;
 pub use serde_json::Value as JsonValue;
 use std::fmt;
 use std::io::Error as IoError;
+use std::time::Duration;
 
+pub mod client;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod handler;
+pub mod inflight;
+#[cfg(feature = "lint")]
+pub mod lint;
 pub mod method;
+#[cfg(feature = "testing")]
+pub mod mock;
+#[cfg(feature = "openrpc")]
+pub mod openrpc;
+pub mod params;
 pub mod parser;
+mod ratelimit;
+pub mod session;
+mod streaming;
+mod timeout;
+#[cfg(feature = "transport")]
+pub mod transport;
 
 #[derive(Debug)]
 pub enum ErrorVariant {
     RwLockPoisoned,
     MethodSignatureNotFound(String),
+    MethodDisabled(String),
+    ReservedMethodSignature(String),
+    MethodAlreadyRegistered(String),
     JsonParseError(JsonError),
+    InvalidRequest(String),
     InvalidJsonRpcVersion,
     InvalidJsonRpcId,
+    DuplicateRequestId(JsonValue),
+    RateLimited(String, Duration),
+    Timeout(String, Duration),
+    IdMismatch(JsonValue, JsonValue),
+    NoResponse(JsonValue),
+    UnmatchedResponse(JsonValue),
+    EmptyBatch,
+    InvalidBatchSlot(usize),
     ResponseCannotContainResultAndError,
     ResponseMustContainResultOrError,
+    InvalidParams(String),
+    InvalidErrorCode(i32),
+    UnsupportedEncoding(String),
+    MessageTooLarge(usize),
+    ErrorResponse(JrpcError),
     NoRequestProvided,
+    MethodError(String, Box<ErrorVariant>),
+    WithBacktrace(Box<ErrorVariant>, String),
     IoError(IoError),
     InternalError,
     InternalErrorMessage(String),
 }
 
+impl ErrorVariant {
+    /// Signature of the method that failed, if the error was raised after dispatching a request
+    pub fn get_method(&self) -> Option<&String> {
+        match self {
+            ErrorVariant::MethodError(s, _) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Underlying error, stripped of the dispatch context
+    pub fn get_cause(&self) -> &ErrorVariant {
+        match self {
+            ErrorVariant::MethodError(_, e) => e.get_cause(),
+            ErrorVariant::WithBacktrace(e, _) => e.get_cause(),
+            _ => self,
+        }
+    }
+
+    /// Whether the error is caused by the client, see [DispatchError]
+    pub(crate) fn is_protocol(&self) -> bool {
+        match self {
+            ErrorVariant::MethodSignatureNotFound(_)
+            | ErrorVariant::MethodDisabled(_)
+            | ErrorVariant::JsonParseError(_)
+            | ErrorVariant::InvalidRequest(_)
+            | ErrorVariant::InvalidJsonRpcVersion
+            | ErrorVariant::InvalidJsonRpcId
+            | ErrorVariant::DuplicateRequestId(_)
+            | ErrorVariant::RateLimited(_, _)
+            | ErrorVariant::Timeout(_, _)
+            | ErrorVariant::EmptyBatch
+            | ErrorVariant::InvalidParams(_)
+            | ErrorVariant::UnsupportedEncoding(_)
+            | ErrorVariant::MessageTooLarge(_) => true,
+            ErrorVariant::RwLockPoisoned
+            | ErrorVariant::IoError(_)
+            | ErrorVariant::IdMismatch(_, _)
+            | ErrorVariant::NoResponse(_)
+            | ErrorVariant::UnmatchedResponse(_)
+            | ErrorVariant::ResponseCannotContainResultAndError
+            | ErrorVariant::ResponseMustContainResultOrError
+            | ErrorVariant::NoRequestProvided
+            | ErrorVariant::InvalidBatchSlot(_)
+            | ErrorVariant::ReservedMethodSignature(_)
+            | ErrorVariant::MethodAlreadyRegistered(_)
+            | ErrorVariant::InvalidErrorCode(_)
+            | ErrorVariant::ErrorResponse(_)
+            | ErrorVariant::InternalError
+            | ErrorVariant::InternalErrorMessage(_) => false,
+            ErrorVariant::MethodError(_, e) => e.is_protocol(),
+            ErrorVariant::WithBacktrace(e, _) => e.is_protocol(),
+        }
+    }
+
+    /// Underlying error, stripped of the dispatch context, see
+    /// [get_cause](ErrorVariant::get_cause)
+    pub fn into_cause(self) -> ErrorVariant {
+        match self {
+            ErrorVariant::MethodError(_, e) => e.into_cause(),
+            ErrorVariant::WithBacktrace(e, _) => e.into_cause(),
+            e => e,
+        }
+    }
+}
+
+/// Failure of a dispatch, split by who is at fault
+///
+/// Protocol errors are caused by the client and are answered with an error object, while
+/// internal faults are problems of the server that should be handled or logged on its side,
+/// without leaking to the client. Failures of a registered method are told apart from both, along
+/// with the signature of the method: they are answered with the error the method raised, such as
+/// `-32602` for `InvalidParams` or `-32603` for `InternalError`. See
+/// [dispatch](handler::JrpcHandler::dispatch).
+///
+/// Errors raised outside of a method are classified by variant.
+///
+/// * Protocol: `MethodSignatureNotFound`, `MethodDisabled`, `JsonParseError`, `InvalidRequest`,
+///   `InvalidJsonRpcVersion`, `InvalidJsonRpcId`, `DuplicateRequestId`, `RateLimited`, `Timeout`,
+///   `EmptyBatch`, `InvalidParams`, `UnsupportedEncoding` and `MessageTooLarge`.
+/// * Internal: `RwLockPoisoned`, `IoError`, `IdMismatch`, `NoResponse`, `UnmatchedResponse`,
+///   `ResponseCannotContainResultAndError`, `ResponseMustContainResultOrError`,
+///   `NoRequestProvided`, `InvalidBatchSlot`, `ReservedMethodSignature`,
+///   `MethodAlreadyRegistered`, `InvalidErrorCode`, `ErrorResponse`, `InternalError` and
+///   `InternalErrorMessage`. In a dispatch they all mean the server is broken.
+///
+/// `WithBacktrace` is classified by the error it wraps.
+#[derive(Debug)]
+pub enum DispatchError {
+    Protocol(ErrorVariant),
+    Method(String, ErrorVariant),
+    Internal(ErrorVariant),
+}
+
+impl DispatchError {
+    pub fn is_internal(&self) -> bool {
+        match self {
+            DispatchError::Internal(_) => true,
+            DispatchError::Protocol(_) | DispatchError::Method(_, _) => false,
+        }
+    }
+
+    /// Signature of the method that failed, for a method failure
+    pub fn get_method(&self) -> Option<&String> {
+        match self {
+            DispatchError::Method(s, _) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn into_inner(self) -> ErrorVariant {
+        match self {
+            DispatchError::Protocol(e) => e,
+            DispatchError::Method(_, e) => e,
+            DispatchError::Internal(e) => e,
+        }
+    }
+}
+
+impl From<ErrorVariant> for DispatchError {
+    fn from(error: ErrorVariant) -> Self {
+        match error {
+            ErrorVariant::MethodError(method, e) => DispatchError::Method(method, e.into_cause()),
+            error if error.is_protocol() => DispatchError::Protocol(error),
+            error => DispatchError::Internal(error),
+        }
+    }
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DispatchError::Protocol(e) => write!(f, "Protocol error: {}", e),
+            DispatchError::Method(s, e) => write!(f, "Method '{}' failed: {}", s, e),
+            DispatchError::Internal(e) => write!(f, "Internal fault: {}", e),
+        }
+    }
+}
+
+impl From<IoError> for ErrorVariant {
+    fn from(error: IoError) -> Self {
+        ErrorVariant::IoError(error)
+    }
+}
+
+impl From<JsonError> for ErrorVariant {
+    fn from(error: JsonError) -> Self {
+        ErrorVariant::JsonParseError(error)
+    }
+}
+
 impl fmt::Display for ErrorVariant {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ErrorVariant::MethodSignatureNotFound(s) => {
                 write!(f, "Method signature '{}' not found", s)
             }
+            ErrorVariant::MethodDisabled(s) => write!(f, "Method '{}' is disabled", s),
+            ErrorVariant::ReservedMethodSignature(s) => {
+                write!(
+                    f,
+                    "Method signature '{}' is reserved for system extensions",
+                    s
+                )
+            }
+            ErrorVariant::MethodAlreadyRegistered(s) => {
+                write!(f, "Method signature '{}' is already registered", s)
+            }
+            ErrorVariant::DuplicateRequestId(id) => {
+                write!(f, "A request with id {} is already in flight", id)
+            }
+            ErrorVariant::RateLimited(s, retry_after) => write!(
+                f,
+                "Rate limit of '{}' exceeded, retry after {}ms",
+                s,
+                retry_after.as_millis()
+            ),
+            ErrorVariant::Timeout(s, timeout) => write!(
+                f,
+                "Method '{}' timed out after {}ms",
+                s,
+                timeout.as_millis()
+            ),
+            ErrorVariant::IdMismatch(expected, found) => {
+                write!(f, "Expected response id {}, found {}", expected, found)
+            }
+            ErrorVariant::NoResponse(id) => write!(f, "No response to the request with id {}", id),
+            ErrorVariant::UnmatchedResponse(id) => {
+                write!(f, "No pending call matches the response with id {}", id)
+            }
+            ErrorVariant::InvalidRequest(s) => write!(f, "Invalid request: {}", s),
+            ErrorVariant::InvalidParams(s) => write!(f, "Invalid params: {}", s),
+            ErrorVariant::MessageTooLarge(limit) => {
+                write!(f, "Message exceeds the limit of {} bytes", limit)
+            }
+            ErrorVariant::InvalidErrorCode(c) => {
+                write!(f, "Error code {} is reserved by the specification", c)
+            }
+            ErrorVariant::ErrorResponse(e) => {
+                write!(f, "Error response {}: {}", e.get_code(), e.get_message())
+            }
+            ErrorVariant::MethodError(s, e) => write!(f, "Method '{}' failed: {}", s, e),
+            ErrorVariant::WithBacktrace(e, _) => write!(f, "{}", e),
             ErrorVariant::InternalErrorMessage(s) => write!(f, "An error ocurred: {}", s),
             _ => write!(f, "{:?}", self),
         }
@@ -284,7 +562,7 @@ macro_rules! generate_method {
                 &self,
                 request: JrpcRequest,
             ) -> Result<
-                Box<'r + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
+                Box<dyn 'r + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
                 ErrorVariant,
             > {
                 Ok(Box::new(self.clone_with_request(request)?))
@@ -293,6 +571,75 @@ macro_rules! generate_method {
     };
 }
 
+/// Define a method with an `async fn`, instead of implementing `Future::poll`
+///
+/// The function takes `&self` and the request, and is called on a clone of the method for each
+/// request. Like [generate_method_with_data_and_future], the method may hold some data, available
+/// through `get_data`.
+///
+/// ```ignore
+/// generate_async_method!(
+///     Echo,
+///     async fn handle(&self, request: JrpcRequest) -> Result<Option<JrpcResponse>, ErrorVariant> {
+///         let result = JrpcResponseParam::generate_result(request.params_or_null())?;
+///         request.generate_response(result).map(Some)
+///     }
+/// );
+/// ```
+#[cfg(feature = "compat")]
+#[macro_export]
+macro_rules! generate_async_method {
+    (
+        $struct_identifier:ident,
+        async fn $fn_identifier:ident(&$self:ident, $request:ident: JrpcRequest) -> $output:ty
+        $body:block
+    ) => {
+        $crate::generate_async_method!(
+            $struct_identifier,
+            (),
+            async fn $fn_identifier(&$self, $request: JrpcRequest) -> $output $body
+        );
+    };
+    (
+        $struct_identifier:ident,
+        $data:ty,
+        async fn $fn_identifier:ident(&$self:ident, $request:ident: JrpcRequest) -> $output:ty
+        $body:block
+    ) => {
+        #[derive(Debug, Clone)]
+        pub struct $struct_identifier {
+            data: $data,
+        }
+
+        impl $struct_identifier {
+            pub fn new(data: $data) -> Result<Self, ErrorVariant> {
+                Ok($struct_identifier { data })
+            }
+
+            pub fn get_data(&self) -> &$data {
+                &self.data
+            }
+
+            async fn $fn_identifier(&$self, $request: JrpcRequest) -> $output $body
+        }
+
+        impl<'r> JrpcMethodTrait<'r> for $struct_identifier {
+            fn generate_future(
+                &self,
+                request: JrpcRequest,
+            ) -> Result<
+                Box<dyn 'r + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
+                ErrorVariant,
+            > {
+                let method = self.clone();
+                Ok($crate::compat::response_future(async move {
+                    method.$fn_identifier(request).await
+                }))
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! generate_method_with_data_and_future {
     ($struct_identifier:ident, $data:ty, $future:item) => {
@@ -340,7 +687,7 @@ macro_rules! generate_method_with_data_and_future {
                 &self,
                 request: JrpcRequest,
             ) -> Result<
-                Box<'r + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
+                Box<dyn 'r + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
                 ErrorVariant,
             > {
                 Ok(Box::new(self.clone_with_request(request)?))
@@ -396,7 +743,7 @@ macro_rules! generate_method_with_lifetime_data_and_future {
                 &self,
                 request: JrpcRequest,
             ) -> Result<
-                Box<$lifetime + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
+                Box<dyn $lifetime + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>,
                 ErrorVariant,
             > {
                 Ok(Box::new(self.clone_with_request(request)?))
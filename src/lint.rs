@@ -0,0 +1,172 @@
+//! Validation reporting every spec violation of a message, for linters and tooling
+//!
+//! Unlike [validate](crate::JrpcRequest::validate), which stops at the first error, these
+//! functions check the raw JSON and collect all the issues found.
+use crate::JsonValue;
+use serde_json::Map;
+use std::fmt;
+
+/// Violation of the specification found by [lint_request] or [lint_response]
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintIssue {
+    /// The message is not valid JSON, with the parser error
+    InvalidJson(String),
+    /// The message is not a JSON object
+    NotAnObject,
+    /// `jsonrpc` is missing or not exactly `"2.0"`
+    InvalidVersion(Option<JsonValue>),
+    /// `method` is missing or not a string
+    InvalidMethod(Option<JsonValue>),
+    /// `params` is neither an array nor an object
+    InvalidParams(JsonValue),
+    /// `id` is missing from a response
+    MissingId,
+    /// `id` is not a string, a number or `null`
+    InvalidId(JsonValue),
+    /// A response contains both `result` and `error`
+    ResultAndError,
+    /// A response contains neither `result` nor `error`
+    MissingResultAndError,
+    /// The error object of a response is malformed, with the reason
+    InvalidError(String),
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintIssue::InvalidJson(e) => write!(f, "Invalid JSON: {}", e),
+            LintIssue::NotAnObject => write!(f, "The message is not an object"),
+            LintIssue::InvalidVersion(None) => write!(f, "Missing member 'jsonrpc'"),
+            LintIssue::InvalidVersion(Some(v)) => {
+                write!(f, "Member 'jsonrpc' must be \"2.0\", found {}", v)
+            }
+            LintIssue::InvalidMethod(None) => write!(f, "Missing member 'method'"),
+            LintIssue::InvalidMethod(Some(v)) => {
+                write!(f, "Member 'method' must be a string, found {}", v)
+            }
+            LintIssue::InvalidParams(v) => {
+                write!(
+                    f,
+                    "Member 'params' must be an array or an object, found {}",
+                    v
+                )
+            }
+            LintIssue::MissingId => write!(f, "Missing member 'id'"),
+            LintIssue::InvalidId(v) => {
+                write!(
+                    f,
+                    "Member 'id' must be a string, a number or null, found {}",
+                    v
+                )
+            }
+            LintIssue::ResultAndError => write!(f, "Both 'result' and 'error' are present"),
+            LintIssue::MissingResultAndError => {
+                write!(f, "Neither 'result' nor 'error' is present")
+            }
+            LintIssue::InvalidError(e) => write!(f, "Invalid error object: {}", e),
+        }
+    }
+}
+
+/// Every spec violation of a request or notification, empty if it is valid
+pub fn lint_request(json: &str) -> Vec<LintIssue> {
+    let message = match parse_object(json) {
+        Ok(m) => m,
+        Err(issue) => return vec![issue],
+    };
+
+    let mut issues = vec![];
+    lint_version(&message, &mut issues);
+
+    match message.get("method") {
+        Some(JsonValue::String(_)) => (),
+        method => issues.push(LintIssue::InvalidMethod(method.cloned())),
+    }
+
+    match message.get("params") {
+        None | Some(JsonValue::Array(_)) | Some(JsonValue::Object(_)) => (),
+        Some(params) => issues.push(LintIssue::InvalidParams(params.clone())),
+    }
+
+    if let Some(id) = message.get("id") {
+        lint_id(id, &mut issues);
+    }
+
+    issues
+}
+
+/// Every spec violation of a response, empty if it is valid
+pub fn lint_response(json: &str) -> Vec<LintIssue> {
+    let message = match parse_object(json) {
+        Ok(m) => m,
+        Err(issue) => return vec![issue],
+    };
+
+    let mut issues = vec![];
+    lint_version(&message, &mut issues);
+
+    match (message.get("result"), message.get("error")) {
+        (Some(_), Some(_)) => issues.push(LintIssue::ResultAndError),
+        (None, None) => issues.push(LintIssue::MissingResultAndError),
+        _ => (),
+    }
+
+    if let Some(error) = message.get("error") {
+        lint_error(error, &mut issues);
+    }
+
+    match message.get("id") {
+        Some(id) => lint_id(id, &mut issues),
+        None => issues.push(LintIssue::MissingId),
+    }
+
+    issues
+}
+
+fn parse_object(json: &str) -> Result<Map<String, JsonValue>, LintIssue> {
+    match serde_json::from_str::<JsonValue>(json) {
+        Ok(JsonValue::Object(message)) => Ok(message),
+        Ok(_) => Err(LintIssue::NotAnObject),
+        Err(e) => Err(LintIssue::InvalidJson(e.to_string())),
+    }
+}
+
+fn lint_version(message: &Map<String, JsonValue>, issues: &mut Vec<LintIssue>) {
+    match message.get("jsonrpc") {
+        Some(JsonValue::String(v)) if v == "2.0" => (),
+        version => issues.push(LintIssue::InvalidVersion(version.cloned())),
+    }
+}
+
+// https://www.jsonrpc.org/specification#id1
+fn lint_id(id: &JsonValue, issues: &mut Vec<LintIssue>) {
+    match id {
+        JsonValue::String(_) | JsonValue::Number(_) | JsonValue::Null => (),
+        _ => issues.push(LintIssue::InvalidId(id.clone())),
+    }
+}
+
+// https://www.jsonrpc.org/specification#error_object
+fn lint_error(error: &JsonValue, issues: &mut Vec<LintIssue>) {
+    let error = match error {
+        JsonValue::Object(error) => error,
+        _ => {
+            issues.push(LintIssue::InvalidError("not an object".to_string()));
+            return;
+        }
+    };
+
+    match error.get("code") {
+        Some(code) if code.is_i64() => (),
+        _ => issues.push(LintIssue::InvalidError(
+            "'code' must be an integer".to_string(),
+        )),
+    }
+
+    match error.get("message") {
+        Some(JsonValue::String(_)) => (),
+        _ => issues.push(LintIssue::InvalidError(
+            "'message' must be a string".to_string(),
+        )),
+    }
+}
@@ -1,9 +1,223 @@
+use crate::futures::future;
 use crate::futures::prelude::*;
-use crate::{ErrorVariant, JrpcRequest, JrpcResponse};
+use crate::{ErrorVariant, JrpcError, JrpcRequest, JrpcResponse, JsonValue};
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
 
+/// Data attached by the transport to a message that isn't part of the request, such as the
+/// address of the client or an authentication token
+///
+/// See [handle_message_with_context](crate::JrpcHandler::handle_message_with_context).
+#[derive(Clone, Default)]
+pub struct JrpcContext(Option<Arc<dyn Any + Send + Sync>>);
+
+impl JrpcContext {
+    pub fn new<C: Any + Send + Sync>(context: C) -> Self {
+        JrpcContext(Some(Arc::new(context)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// The context, if there is one of type `C`
+    pub fn get<C: Any>(&self) -> Option<&C> {
+        self.0.as_ref().and_then(|context| context.downcast_ref())
+    }
+}
+
+impl fmt::Debug for JrpcContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.is_empty() {
+            true => f.write_str("JrpcContext(None)"),
+            false => f.write_str("JrpcContext(..)"),
+        }
+    }
+}
+
+/// A method that can be registered in a [JrpcHandler](crate::JrpcHandler)
+///
+/// The handler is shared among threads, so the methods registered in it must be `Send + Sync`,
+/// but this trait and the other method traits don't require it. The futures they generate are not
+/// required to be.
 pub trait JrpcMethodTrait<'a> {
     fn generate_future(
         &self,
         request: JrpcRequest,
-    ) -> Result<Box<'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>;
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>;
+
+    /// Generate the future of `request` along with the context of its message, empty if none was
+    /// given. Ignores the context by default
+    fn generate_future_with_context(
+        &self,
+        request: JrpcRequest,
+        context: JrpcContext,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let _ = context;
+        self.generate_future(request)
+    }
+}
+
+/// Method delegating each request to the method picked for it by a closure
+pub(crate) struct DispatchedMethod<F>(pub(crate) F);
+
+impl<'a, F> JrpcMethodTrait<'a> for DispatchedMethod<F>
+where
+    F: Fn(&JrpcRequest) -> Box<dyn JrpcMethodTrait<'a> + 'a>,
+{
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        (self.0)(&request).generate_future(request)
+    }
+
+    fn generate_future_with_context(
+        &self,
+        request: JrpcRequest,
+        context: JrpcContext,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        (self.0)(&request).generate_future_with_context(request, context)
+    }
+}
+
+/// Method resolving each request right away with the outcome of a closure
+pub(crate) struct FnMethod<F>(pub(crate) F);
+
+impl<'a, F> JrpcMethodTrait<'a> for FnMethod<F>
+where
+    F: Fn(JrpcRequest) -> Result<Option<JrpcResponse>, ErrorVariant>,
+{
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        Ok(Box::new(future::result((self.0)(request))))
+    }
+}
+
+/// What a method resolves to, making explicit whether the request is answered
+///
+/// Converts from and into the `Option<JrpcResponse>` resolved by [JrpcMethodTrait] futures, where
+/// `None` stands for [NoResponse](MethodOutcome::NoResponse).
+#[derive(Debug, Clone, PartialEq)]
+pub enum MethodOutcome {
+    /// Reply with the response
+    Response(JrpcResponse),
+    /// Send nothing back, e.g. for a notification
+    NoResponse,
+}
+
+impl MethodOutcome {
+    pub fn is_response(&self) -> bool {
+        match self {
+            MethodOutcome::Response(_) => true,
+            MethodOutcome::NoResponse => false,
+        }
+    }
+
+    pub fn into_response(self) -> Option<JrpcResponse> {
+        self.into()
+    }
+}
+
+impl From<Option<JrpcResponse>> for MethodOutcome {
+    fn from(response: Option<JrpcResponse>) -> Self {
+        match response {
+            Some(response) => MethodOutcome::Response(response),
+            None => MethodOutcome::NoResponse,
+        }
+    }
+}
+
+impl From<MethodOutcome> for Option<JrpcResponse> {
+    fn from(outcome: MethodOutcome) -> Self {
+        match outcome {
+            MethodOutcome::Response(response) => Some(response),
+            MethodOutcome::NoResponse => None,
+        }
+    }
+}
+
+impl From<JrpcResponse> for MethodOutcome {
+    fn from(response: JrpcResponse) -> Self {
+        MethodOutcome::Response(response)
+    }
+}
+
+/// A method whose future resolves to a [MethodOutcome], see
+/// [register_outcome_method](crate::JrpcHandler::register_outcome_method)
+pub trait JrpcOutcomeMethodTrait<'a> {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = MethodOutcome, Error = ErrorVariant>>, ErrorVariant>;
+}
+
+/// Method adapting the outcome of a [JrpcOutcomeMethodTrait] to the one of a [JrpcMethodTrait]
+pub(crate) struct OutcomeMethod<M>(pub(crate) M);
+
+impl<'a, M: JrpcOutcomeMethodTrait<'a>> JrpcMethodTrait<'a> for OutcomeMethod<M> {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let future = self.0.generate_future(request)?;
+        Ok(Box::new(future.map(MethodOutcome::into_response)))
+    }
+}
+
+/// A fire-and-forget method, only accepting notifications, see
+/// [register_notification](crate::JrpcHandler::register_notification)
+pub trait JrpcNotificationMethodTrait<'a> {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = (), Error = ErrorVariant>>, ErrorVariant>;
+}
+
+/// Method running a [JrpcNotificationMethodTrait], answering requests with an id with an
+/// invalid request error instead
+pub(crate) struct NotificationMethod<M>(pub(crate) M);
+
+impl<'a, M: JrpcNotificationMethodTrait<'a>> JrpcMethodTrait<'a> for NotificationMethod<M> {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        if !request.is_notification() {
+            let error = ErrorVariant::InvalidRequest(format!(
+                "Method '{}' only accepts notifications",
+                request.get_method()
+            ));
+            let response = JrpcResponse::error_with_request_id(JrpcError::from(error), &request);
+            return Ok(Box::new(future::ok(Some(response))));
+        }
+
+        let future = self.0.generate_future(request)?;
+        Ok(Box::new(future.map(|_| None)))
+    }
+}
+
+/// Array params of a request, yielded element by element as they are parsed
+pub type ParamsStream = Box<dyn Stream<Item = JsonValue, Error = ErrorVariant> + Send>;
+
+/// A method receiving its array params as a [ParamsStream], see
+/// [register_streaming_method](crate::JrpcHandler::register_streaming_method)
+///
+/// Meant for methods taking very large arrays, e.g. bulk imports, that can process the elements
+/// incrementally instead of holding the whole array in memory. The future resolves to the result
+/// of the response, built by the handler once the id of the request is read.
+pub trait JrpcStreamingMethodTrait<'a> {
+    fn generate_future(
+        &self,
+        params: ParamsStream,
+    ) -> Result<Box<dyn 'a + Future<Item = JsonValue, Error = ErrorVariant>>, ErrorVariant>;
 }
@@ -0,0 +1,142 @@
+use crate::futures::prelude::*;
+use crate::{ErrorVariant, JrpcClient, JrpcHandler, JrpcRequest, JrpcResponse, JsonValue};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Drive a request through the whole handler pipeline, from serialization to the resolved
+/// response, blocking the current thread
+pub fn round_trip(
+    handler: &JrpcHandler,
+    request: &JrpcRequest,
+) -> Result<Option<JrpcResponse>, ErrorVariant> {
+    let message = serde_json::to_string(request)?;
+
+    handler
+        .handle_message(message)
+        .and_then(|future| future.wait())
+}
+
+/// Client connected in-process to `handler`, without any networking
+///
+/// Messages sent by the client are dispatched by `handler`, single or batch, and the replies are
+/// routed back to the client. Dispatch happens when the client future is polled, on its thread.
+pub fn loopback<'a>(handler: JrpcHandler<'a>) -> JrpcClient<'a> {
+    JrpcClient::new(move |message: String| {
+        let reply = handler.handle_batch_message(message)?.and_then(|outcome| {
+            outcome
+                .map(|outcome| serde_json::to_string(&outcome).map_err(ErrorVariant::from))
+                .transpose()
+        });
+
+        let reply: Box<dyn 'a + Future<Item = Option<String>, Error = ErrorVariant>> =
+            Box::new(reply);
+        Ok(reply)
+    })
+}
+
+/// Build the request that would be answered with `response` when sent to `method`
+///
+/// Useful for golden-file tests, where only the expected responses are recorded.
+pub fn request_for_response<T: ToString>(
+    response: &JrpcResponse,
+    method: T,
+    params: Option<JsonValue>,
+) -> Result<JrpcRequest, ErrorVariant> {
+    JrpcRequest::new(method, params, Some(response.get_id().clone()))
+}
+
+/// Sender of a message in a recorded transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// A request, notification or batch of them
+    ClientToServer,
+    /// A response or batch of responses
+    ServerToClient,
+}
+
+/// Spec violation found by [validate_transcript], with the index of the offending message
+#[derive(Debug)]
+pub enum TranscriptError {
+    /// The message, or an element of its batch, is not a valid request or response
+    InvalidMessage(usize, ErrorVariant),
+    /// The request reuses the id of a request that was not answered yet
+    DuplicateRequestId(usize, JsonValue),
+    /// The response doesn't answer any pending request
+    UnexpectedResponse(usize, JsonValue),
+    /// The request was never answered
+    MissingResponse(usize, JsonValue),
+}
+
+/// Check a recorded conversation for compliance with the specification
+///
+/// Every request must be answered by a single response with its id, and every response must
+/// answer a pending request. Error responses with a `null` id are accepted on their own, since
+/// they answer messages that couldn't be parsed. Every violation is reported, not only the first.
+pub fn validate_transcript<T: AsRef<str>>(
+    messages: &[(Direction, T)],
+) -> Result<(), Vec<TranscriptError>> {
+    let mut errors = vec![];
+    let mut pending: HashMap<String, (usize, JsonValue)> = HashMap::new();
+
+    for (index, (direction, message)) in messages.iter().enumerate() {
+        let elements = match serde_json::from_str::<JsonValue>(message.as_ref()) {
+            Ok(JsonValue::Array(elements)) => elements,
+            Ok(value) => vec![value],
+            Err(e) => {
+                errors.push(TranscriptError::InvalidMessage(
+                    index,
+                    ErrorVariant::JsonParseError(e),
+                ));
+                continue;
+            }
+        };
+
+        for element in elements {
+            match direction {
+                Direction::ClientToServer => match JrpcRequest::from_value(element) {
+                    Ok(ref request) if request.is_notification() => (),
+                    Ok(request) => {
+                        let id = request.get_id().clone().unwrap_or(JsonValue::Null);
+                        match pending.entry(id.to_string()) {
+                            Entry::Occupied(_) => {
+                                errors.push(TranscriptError::DuplicateRequestId(index, id))
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert((index, id));
+                            }
+                        }
+                    }
+                    Err(e) => errors.push(TranscriptError::InvalidMessage(index, e)),
+                },
+                Direction::ServerToClient => {
+                    let response = JrpcResponse::from_value(element);
+
+                    match response {
+                        Ok(response) => {
+                            let id = response.get_id();
+                            let answers_unparsed = id.is_null() && response.get_error().is_some();
+                            if pending.remove(&id.to_string()).is_none() && !answers_unparsed {
+                                errors.push(TranscriptError::UnexpectedResponse(index, id.clone()));
+                            }
+                        }
+                        Err(e) => errors.push(TranscriptError::InvalidMessage(index, e)),
+                    }
+                }
+            }
+        }
+    }
+
+    let mut missing: Vec<(usize, JsonValue)> = pending.into_values().collect();
+    missing.sort_by_key(|(index, _)| *index);
+    errors.extend(
+        missing
+            .into_iter()
+            .map(|(index, id)| TranscriptError::MissingResponse(index, id)),
+    );
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,110 @@
+//! [OpenRPC](https://spec.open-rpc.org) description of the methods of a handler
+//!
+//! Schemas are attached to registered methods with
+//! [set_method_schema](crate::JrpcHandler::set_method_schema), and the document is assembled by
+//! [to_openrpc_document](crate::JrpcHandler::to_openrpc_document).
+use crate::JsonValue;
+use serde::{Deserialize, Serialize};
+
+/// Version of the OpenRPC specification the documents conform to
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// Name and JSON Schema of a param or of a result
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ContentDescriptor {
+    pub name: String,
+    pub schema: JsonValue,
+}
+
+impl ContentDescriptor {
+    pub fn new<T: ToString>(name: T, schema: JsonValue) -> Self {
+        ContentDescriptor {
+            name: name.to_string(),
+            schema,
+        }
+    }
+}
+
+/// Description of a method, serialized as an OpenRPC method object without its name
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MethodSchema {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(default)]
+    params: Vec<ContentDescriptor>,
+    result: ContentDescriptor,
+}
+
+impl Default for MethodSchema {
+    /// No params, and a result accepting any value
+    fn default() -> Self {
+        MethodSchema {
+            summary: None,
+            params: vec![],
+            result: ContentDescriptor::new("result", JsonValue::Object(Default::default())),
+        }
+    }
+}
+
+impl MethodSchema {
+    pub fn new() -> Self {
+        MethodSchema::default()
+    }
+
+    pub fn with_summary<T: ToString>(mut self, summary: T) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    /// Append a param, in the position it takes in the positional form of the params
+    pub fn with_param<T: ToString>(mut self, name: T, schema: JsonValue) -> Self {
+        self.params.push(ContentDescriptor::new(name, schema));
+        self
+    }
+
+    pub fn with_result<T: ToString>(mut self, name: T, schema: JsonValue) -> Self {
+        self.result = ContentDescriptor::new(name, schema);
+        self
+    }
+
+    pub fn get_summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    pub fn get_params(&self) -> &Vec<ContentDescriptor> {
+        &self.params
+    }
+
+    pub fn get_result(&self) -> &ContentDescriptor {
+        &self.result
+    }
+}
+
+#[derive(Serialize)]
+struct MethodObject<'s> {
+    name: &'s str,
+    #[serde(flatten)]
+    schema: &'s MethodSchema,
+}
+
+/// OpenRPC document listing `methods`, sorted by name
+pub(crate) fn document(mut methods: Vec<(String, MethodSchema)>) -> JsonValue {
+    methods.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let methods: Vec<JsonValue> = methods
+        .iter()
+        .map(|(name, schema)| {
+            serde_json::to_value(MethodObject { name, schema })
+                .expect("A method schema always serializes to a JSON object")
+        })
+        .collect();
+
+    serde_json::json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": "JSON-RPC API",
+            "version": "1.0.0",
+        },
+        "methods": methods,
+    })
+}
@@ -0,0 +1,136 @@
+use crate::{ErrorVariant, JsonValue};
+use serde::de::DeserializeOwned;
+use serde_json::Map;
+
+/// Expected shape of the params of a method, checked before dispatching requests to it
+///
+/// See [register_method_with_schema](crate::JrpcHandler::register_method_with_schema).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamsSpec {
+    /// Positional params with exactly this number of elements
+    Arity(usize),
+    /// Named params with at least these members
+    Keys(Vec<String>),
+}
+
+impl ParamsSpec {
+    pub fn arity(arity: usize) -> Self {
+        ParamsSpec::Arity(arity)
+    }
+
+    pub fn keys<I: IntoIterator<Item = T>, T: ToString>(keys: I) -> Self {
+        ParamsSpec::Keys(keys.into_iter().map(|key| key.to_string()).collect())
+    }
+
+    /// Check `params`, failing with `ErrorVariant::InvalidParams` if they don't match
+    pub fn check(&self, params: Option<&JsonValue>) -> Result<(), ErrorVariant> {
+        match (self, params) {
+            (ParamsSpec::Arity(arity), Some(JsonValue::Array(params))) => {
+                if params.len() != *arity {
+                    return Err(ErrorVariant::InvalidParams(format!(
+                        "Expected {} positional parameters, got {}",
+                        arity,
+                        params.len()
+                    )));
+                }
+                Ok(())
+            }
+            (ParamsSpec::Arity(arity), _) => Err(ErrorVariant::InvalidParams(format!(
+                "Expected {} positional parameters",
+                arity
+            ))),
+            (ParamsSpec::Keys(keys), Some(JsonValue::Object(params))) => {
+                match keys.iter().find(|key| !params.contains_key(*key)) {
+                    Some(key) => Err(ErrorVariant::InvalidParams(format!(
+                        "Missing parameter '{}'",
+                        key
+                    ))),
+                    None => Ok(()),
+                }
+            }
+            (ParamsSpec::Keys(_), _) => Err(ErrorVariant::InvalidParams(
+                "Expected named parameters".to_string(),
+            )),
+        }
+    }
+}
+
+/// Deserialize params given either by name or by position into `T`
+///
+/// Positional params are assigned the names of `order` before deserializing, so the fields of `T`
+/// are matched by key in both cases.
+pub fn deserialize_params<T: DeserializeOwned>(
+    params: &JsonValue,
+    order: &[&str],
+) -> Result<T, ErrorVariant> {
+    let named = match params {
+        JsonValue::Array(_) => positional_to_named(params.clone(), order)?,
+        _ => params.clone(),
+    };
+
+    serde_json::from_value(named).map_err(|e| ErrorVariant::InvalidParams(e.to_string()))
+}
+
+/// Convert named params into positional params, following the parameter `order` of the target
+/// signature
+///
+/// Every name of `order` must be present, and no other name is accepted.
+pub fn named_to_positional(params: JsonValue, order: &[&str]) -> Result<JsonValue, ErrorVariant> {
+    let mut params = match params {
+        JsonValue::Object(params) => params,
+        _ => {
+            return Err(ErrorVariant::InvalidParams(
+                "Expected named parameters".to_string(),
+            ))
+        }
+    };
+
+    let positional = order
+        .iter()
+        .map(|name| {
+            params
+                .remove(*name)
+                .ok_or_else(|| ErrorVariant::InvalidParams(format!("Missing parameter '{}'", name)))
+        })
+        .collect::<Result<Vec<JsonValue>, ErrorVariant>>()?;
+
+    if let Some(name) = params.keys().next() {
+        return Err(ErrorVariant::InvalidParams(format!(
+            "Unexpected parameter '{}'",
+            name
+        )));
+    }
+
+    Ok(JsonValue::Array(positional))
+}
+
+/// Convert positional params into named params, assigning `names` in order
+///
+/// Trailing names without a param are left out, so optional trailing params may be omitted. Params
+/// beyond the last name are rejected.
+pub fn positional_to_named(params: JsonValue, names: &[&str]) -> Result<JsonValue, ErrorVariant> {
+    let params = match params {
+        JsonValue::Array(params) => params,
+        _ => {
+            return Err(ErrorVariant::InvalidParams(
+                "Expected positional parameters".to_string(),
+            ))
+        }
+    };
+
+    if params.len() > names.len() {
+        return Err(ErrorVariant::InvalidParams(format!(
+            "Expected at most {} parameters, got {}",
+            names.len(),
+            params.len()
+        )));
+    }
+
+    let named = names
+        .iter()
+        .map(|name| name.to_string())
+        .zip(params)
+        .collect::<Map<String, JsonValue>>();
+
+    Ok(JsonValue::Object(named))
+}
@@ -1,5 +1,13 @@
-use crate::{ErrorVariant, JsonValue};
-use serde::{Deserialize, Serialize};
+use crate::{ErrorVariant, IdGenerator, JsonRawValue, JsonValue};
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::ser::{Error as SerError, SerializeMap};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Map;
+use std::backtrace::Backtrace;
+use std::fmt;
+use std::io::Write;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -10,8 +18,85 @@ pub struct JrpcRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     params: Option<JsonValue>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    // An explicit `null` id is a call expecting a response, unlike an absent id
+    #[serde(
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
     id: Option<JsonValue>,
+
+    #[serde(skip)]
+    raw_id: Option<String>,
+
+    #[serde(skip)]
+    matched_signature: Option<String>,
+}
+
+/// Deserialize the first JSON value of `message`, returning the offset of the next byte
+fn parse_prefix(message: &str) -> Result<(JsonValue, usize), ErrorVariant> {
+    let mut stream = serde_json::Deserializer::from_str(message).into_iter::<JsonValue>();
+
+    match stream.next() {
+        Some(parsed) => Ok((parsed?, stream.byte_offset())),
+        // Blank input, reported as the same EOF error as a full parse
+        None => Ok((serde_json::from_str::<JsonValue>(message)?, message.len())),
+    }
+}
+
+/// Length of the compact serialization of `value`, counting escaped characters as one byte
+fn estimate_value_size(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Null => 4,
+        JsonValue::Bool(true) => 4,
+        JsonValue::Bool(false) => 5,
+        JsonValue::Number(n) => n.to_string().len(),
+        JsonValue::String(s) => s.len() + 2,
+        JsonValue::Array(values) => {
+            let commas = values.len().saturating_sub(1);
+            2 + commas + values.iter().map(estimate_value_size).sum::<usize>()
+        }
+        JsonValue::Object(members) => {
+            let commas = members.len().saturating_sub(1);
+            let members: usize = members
+                .iter()
+                .map(|(key, value)| key.len() + 3 + estimate_value_size(value))
+                .sum();
+            2 + commas + members
+        }
+    }
+}
+
+/// Writer discarding its input, counting the bytes written
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Request with a borrowed raw id, to keep its exact textual form
+#[derive(Deserialize)]
+struct JrpcRawId<'m> {
+    #[serde(borrow, default, deserialize_with = "deserialize_present")]
+    id: Option<&'m JsonRawValue>,
+}
+
+/// Deserialize a member that is present, even as `null`, into `Some`
+///
+/// Combined with `#[serde(default)]`, only an absent member is `None`.
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 impl JrpcRequest {
@@ -20,54 +105,177 @@ impl JrpcRequest {
         params: Option<JsonValue>,
         id: Option<JsonValue>,
     ) -> Result<JrpcRequest, ErrorVariant> {
-        let method = method.to_string();
-        let jrpc_request = JrpcRequest {
+        JrpcRequest::new_unchecked(method, params, id).validated()
+    }
+
+    /// Build a request without validating it, e.g. to validate a whole batch at once with
+    /// [validate](JrpcRequest::validate), or to test how invalid requests are handled
+    pub fn new_unchecked<T: ToString>(
+        method: T,
+        params: Option<JsonValue>,
+        id: Option<JsonValue>,
+    ) -> JrpcRequest {
+        JrpcRequest {
             jsonrpc: "2.0".to_string(),
-            method,
+            method: method.to_string(),
             params,
             id,
-        };
+            raw_id: None,
+            matched_signature: None,
+        }
+    }
 
-        jrpc_request.validate()
+    /// Build a request to `method` with the next id of `generator`
+    pub fn new_with_generated_id<T: ToString>(
+        generator: &IdGenerator,
+        method: T,
+        params: Option<JsonValue>,
+    ) -> Result<JrpcRequest, ErrorVariant> {
+        JrpcRequest::new(method, params, Some(generator.next_id()))
+    }
+
+    /// Start building a request to `method`, see [JrpcRequestBuilder]
+    pub fn builder<T: ToString>(method: T) -> JrpcRequestBuilder {
+        JrpcRequestBuilder::new(method)
     }
 
     pub fn prepare_to_send_notification<T: ToString>(
         method: T,
         params: Option<JsonValue>,
     ) -> Result<JrpcRequest, ErrorVariant> {
-        let method = method.to_string();
-        let id = None;
-        let jrpc_request = JrpcRequest {
-            jsonrpc: "2.0".to_string(),
-            method,
-            params,
-            id,
-        };
-
-        jrpc_request.validate()
+        JrpcRequest::new(method, params, None)
     }
 
     pub fn prepare_to_send_request<T: ToString>(
         method: T,
         params: Option<JsonValue>,
     ) -> Result<JrpcRequest, ErrorVariant> {
-        let method = method.to_string();
         let id = Some(JsonValue::String(Uuid::new_v4().to_string()));
-        let jrpc_request = JrpcRequest {
-            jsonrpc: "2.0".to_string(),
-            method,
-            params,
-            id,
-        };
-
-        jrpc_request.validate()
+        JrpcRequest::new(method, params, id)
     }
 
     pub fn parse<F: ToString>(message: F) -> Result<Self, ErrorVariant> {
         let message = message.to_string();
-        serde_json::from_str::<Self>(message.as_str())
-            .map_err(|e| ErrorVariant::JsonParseError(e))
-            .and_then(|parsed| parsed.validate())
+        JrpcRequest::parse_str(message.as_str())
+    }
+
+    /// Parse a borrowed message, without copying it
+    ///
+    /// Fails with `ErrorVariant::JsonParseError` if the message is not valid JSON, and with
+    /// `ErrorVariant::InvalidRequest` if it is valid JSON but not a request object, e.g. without a
+    /// `method`.
+    pub fn parse_str(message: &str) -> Result<Self, ErrorVariant> {
+        let value = serde_json::from_str::<JsonValue>(message)?;
+        JrpcRequest::from_value(value)
+    }
+
+    /// Parse the request at the start of `message`, ignoring anything after it, and return the
+    /// byte offset where it ended
+    ///
+    /// Allows reading back-to-back messages, such as `{...}{...}`, without any delimiter: the
+    /// next message starts at the returned offset.
+    pub fn parse_prefix(message: &str) -> Result<(Self, usize), ErrorVariant> {
+        let (value, offset) = parse_prefix(message)?;
+        JrpcRequest::from_value(value).map(|request| (request, offset))
+    }
+
+    /// Parse a message straight from a byte buffer
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes(message: &[u8]) -> Result<Self, ErrorVariant> {
+        let value = serde_json::from_slice::<JsonValue>(message)?;
+        JrpcRequest::from_value(value)
+    }
+
+    /// Serialize the request into a byte buffer
+    #[cfg(feature = "bytes")]
+    pub fn to_bytes(&self) -> Result<Bytes, ErrorVariant> {
+        Ok(Bytes::from(serde_json::to_vec(self)?))
+    }
+
+    /// Parse a message keeping the exact textual form of its id, such as `1e2` or `100.0`
+    ///
+    /// Responses generated from this request echo the id byte-for-byte, instead of the
+    /// reformatted number.
+    pub fn parse_preserving_id(message: &str) -> Result<Self, ErrorVariant> {
+        JrpcRequest::parse_with(message, false, true)
+    }
+
+    /// Parse a message as [parse_str](JrpcRequest::parse_str), optionally accepting legacy
+    /// versions and keeping the exact textual form of the id
+    pub(crate) fn parse_with(
+        message: &str,
+        lenient: bool,
+        preserve_raw_id: bool,
+    ) -> Result<Self, ErrorVariant> {
+        let value = serde_json::from_str::<JsonValue>(message)?;
+        let raw = Some(message).filter(|_| preserve_raw_id);
+        JrpcRequest::from_value_with(value, raw, lenient)
+    }
+
+    /// Build a request from an already parsed JSON value, keeping the id as written in `raw`, the
+    /// text the value was parsed from
+    pub(crate) fn from_value_with(
+        value: JsonValue,
+        raw: Option<&str>,
+        lenient: bool,
+    ) -> Result<Self, ErrorVariant> {
+        let mut request = if lenient {
+            JrpcRequest::from_value_lenient(value)?
+        } else {
+            JrpcRequest::from_value(value)?
+        };
+
+        if let Some(raw) = raw {
+            let parsed = serde_json::from_str::<JrpcRawId>(raw)
+                .map_err(|e| ErrorVariant::InvalidRequest(e.to_string()))?;
+            request.raw_id = parsed.id.map(|id| id.get().to_string());
+        }
+
+        Ok(request)
+    }
+
+    /// Build a request from an already parsed JSON value, such as an element of a batch
+    pub fn from_value(value: JsonValue) -> Result<Self, ErrorVariant> {
+        serde_json::from_value::<Self>(value)
+            .map_err(|e| ErrorVariant::InvalidRequest(e.to_string()))?
+            .validated()
+    }
+
+    /// Parse a request from a legacy peer omitting the `jsonrpc` version or sending `"2"`, which
+    /// is then normalized to `"2.0"`
+    ///
+    /// Every other rule of [parse](JrpcRequest::parse) still applies.
+    pub fn parse_lenient(message: &str) -> Result<Self, ErrorVariant> {
+        let value = serde_json::from_str::<JsonValue>(message)?;
+        JrpcRequest::from_value_lenient(value)
+    }
+
+    /// Build a request from an already parsed JSON value, accepting legacy versions as
+    /// [parse_lenient](JrpcRequest::parse_lenient)
+    pub fn from_value_lenient(mut value: JsonValue) -> Result<Self, ErrorVariant> {
+        if let JsonValue::Object(request) = &mut value {
+            let legacy = match request.get("jsonrpc") {
+                Some(version) => *version == "2",
+                None => true,
+            };
+
+            if legacy {
+                request.insert("jsonrpc".to_string(), JsonValue::from("2.0"));
+            }
+        }
+
+        JrpcRequest::from_value(value)
+    }
+
+    /// Whether `response` answers this request, comparing the ids as JSON values
+    ///
+    /// A notification has no id and is never answered, so it matches no response. A request with
+    /// a `null` id matches a response with a `null` id.
+    pub fn matches_response(&self, response: &JrpcResponse) -> bool {
+        match self.get_id() {
+            Some(id) => id == response.get_id(),
+            None => false,
+        }
     }
 
     pub fn generate_response(
@@ -77,7 +285,8 @@ impl JrpcRequest {
         JrpcResponse::from_jrpc_request(self, response)
     }
 
-    fn validate(self) -> Result<Self, ErrorVariant> {
+    /// Check the request against the specification
+    pub fn validate(&self) -> Result<(), ErrorVariant> {
         if self.get_jsonrpc() != "2.0" {
             return Err(ErrorVariant::InvalidJsonRpcVersion);
         }
@@ -91,13 +300,52 @@ impl JrpcRequest {
             _ => return Err(ErrorVariant::InvalidJsonRpcId),
         }
 
+        Ok(())
+    }
+
+    fn validated(self) -> Result<Self, ErrorVariant> {
+        self.validate()?;
         Ok(self)
     }
 
+    /// Whether the request has no id, so it must not be answered
+    ///
+    /// A request with a `null` id is not a notification: it's a call answered with a `null` id.
     pub fn is_notification(&self) -> bool {
         self.id.is_none()
     }
 
+    /// Approximate length in bytes of the serialized request, computed without serializing it
+    ///
+    /// The estimate is exact for compact serialization unless strings contain characters that
+    /// must be escaped, which are counted as a single byte. See
+    /// [serialized_size](JrpcRequest::serialized_size) for the exact length.
+    pub fn estimate_size(&self) -> usize {
+        // {"jsonrpc":"2.0","method":""}
+        let mut size = 29 + self.method.len();
+
+        if let Some(params) = &self.params {
+            // ,"params":
+            size += 10 + estimate_value_size(params);
+        }
+
+        if let Some(id) = &self.id {
+            // ,"id":
+            size += 6 + estimate_value_size(id);
+        }
+
+        size
+    }
+
+    /// Exact length in bytes of the compact serialization of the request
+    ///
+    /// The request is serialized, but nothing is allocated for the output.
+    pub fn serialized_size(&self) -> Result<usize, ErrorVariant> {
+        let mut counter = ByteCounter(0);
+        serde_json::to_writer(&mut counter, self)?;
+        Ok(counter.0)
+    }
+
     pub fn get_jsonrpc(&self) -> &String {
         &self.jsonrpc
     }
@@ -110,12 +358,173 @@ impl JrpcRequest {
         &self.params
     }
 
+    /// Params of the request, or `null` if absent
+    pub fn params_or_null(&self) -> JsonValue {
+        self.params.clone().unwrap_or(JsonValue::Null)
+    }
+
+    /// Params of the request, failing with `ErrorVariant::InvalidParams` if absent or `null`
+    pub fn require_params(&self) -> Result<&JsonValue, ErrorVariant> {
+        self.params
+            .as_ref()
+            .filter(|params| !params.is_null())
+            .ok_or_else(|| ErrorVariant::InvalidParams("Expected params".to_string()))
+    }
+
     pub fn get_id(&self) -> &Option<JsonValue> {
         &self.id
     }
+
+    /// Id of the request as an integer, or `None` for a notification or an id of another type
+    pub fn id_as_i64(&self) -> Option<i64> {
+        self.id.as_ref().and_then(JsonValue::as_i64)
+    }
+
+    /// Id of the request as a string, or `None` for a notification or an id of another type
+    pub fn id_as_str(&self) -> Option<&str> {
+        self.id.as_ref().and_then(JsonValue::as_str)
+    }
+
+    /// Exact textual form of the id, if parsed with
+    /// [parse_preserving_id](JrpcRequest::parse_preserving_id)
+    pub fn get_raw_id(&self) -> Option<&str> {
+        self.raw_id.as_deref()
+    }
+
+    /// Registration key of the method this request was dispatched to, set by the handler right
+    /// before calling [generate_future](crate::JrpcMethodTrait::generate_future) if enabled with
+    /// [set_attach_signatures](crate::handler::JrpcHandler::set_attach_signatures)
+    ///
+    /// Signatures are currently matched exactly, so this equals [get_method](JrpcRequest::get_method).
+    /// Once requests can reach a method through an alias or a namespace, it's the key the method
+    /// was registered under, while `get_method` keeps the string sent by the client.
+    pub fn get_matched_signature(&self) -> Option<&str> {
+        self.matched_signature.as_deref()
+    }
+
+    pub(crate) fn set_matched_signature(&mut self, signature: String) {
+        self.matched_signature = Some(signature);
+    }
+
+    /// Deserialize the required named parameter `key`
+    pub fn param<T: DeserializeOwned>(&self, key: &str) -> Result<T, ErrorVariant> {
+        self.named_param(key)?
+            .ok_or_else(|| ErrorVariant::InvalidParams(format!("Missing parameter '{}'", key)))
+            .and_then(|value| JrpcRequest::deserialize_param(key, value))
+    }
+
+    /// Deserialize the optional named parameter `key`, falling back to `default` when it is absent
+    ///
+    /// A parameter that is present but can't be deserialized into `T` is still an error.
+    pub fn param_or<T: DeserializeOwned>(&self, key: &str, default: T) -> Result<T, ErrorVariant> {
+        self.named_param(key)?
+            .map(|value| JrpcRequest::deserialize_param(key, value))
+            .unwrap_or(Ok(default))
+    }
+
+    /// Deserialize the whole params into `T`, such as a struct for named params or a tuple for
+    /// positional ones
+    ///
+    /// Absent params are deserialized as `null`, so `Option<T>` accepts requests without params.
+    /// Failures are reported as `ErrorVariant::InvalidParams`, answered with `-32602`.
+    pub fn get_params_as<T: DeserializeOwned>(&self) -> Result<T, ErrorVariant> {
+        let params = self.params.clone().unwrap_or(JsonValue::Null);
+        serde_json::from_value(params).map_err(|e| ErrorVariant::InvalidParams(e.to_string()))
+    }
+
+    /// Deserialize the whole params into `T`, like [get_params_as](JrpcRequest::get_params_as),
+    /// but failing on members of the params that `T` doesn't expect
+    ///
+    /// Catches misspelled param names, which would otherwise be ignored in favor of defaults. The
+    /// error names every unexpected member, with its path for nested ones.
+    #[cfg(feature = "serde_ignored")]
+    pub fn get_params_as_strict<T: DeserializeOwned>(&self) -> Result<T, ErrorVariant> {
+        let params = self.params.clone().unwrap_or(JsonValue::Null);
+        let mut unexpected = vec![];
+
+        let parsed = serde_ignored::deserialize(params, |path| unexpected.push(path.to_string()))
+            .map_err(|e| ErrorVariant::InvalidParams(e.to_string()))?;
+
+        if !unexpected.is_empty() {
+            return Err(ErrorVariant::InvalidParams(format!(
+                "unexpected field `{}`",
+                unexpected.join("`, `")
+            )));
+        }
+
+        Ok(parsed)
+    }
+
+    /// Deserialize the params into `T`, accepting both named params and positional params in the
+    /// given `order`
+    pub fn get_params_with_order<T: DeserializeOwned>(
+        &self,
+        order: &[&str],
+    ) -> Result<T, ErrorVariant> {
+        let params = self.params.as_ref().unwrap_or(&JsonValue::Null);
+        crate::params::deserialize_params(params, order)
+    }
+
+    /// Positional params, if the params are an array
+    pub fn params_as_array(&self) -> Option<&Vec<JsonValue>> {
+        self.params.as_ref().and_then(JsonValue::as_array)
+    }
+
+    /// Named params, if the params are an object
+    pub fn params_as_object(&self) -> Option<&Map<String, JsonValue>> {
+        self.params.as_ref().and_then(JsonValue::as_object)
+    }
+
+    /// Positional param at `index`, if the params are an array long enough
+    pub fn param_at(&self, index: usize) -> Option<&JsonValue> {
+        self.params_as_array().and_then(|params| params.get(index))
+    }
+
+    /// Named param `key`, if the params are an object containing it
+    pub fn param_named(&self, key: &str) -> Option<&JsonValue> {
+        self.params_as_object().and_then(|params| params.get(key))
+    }
+
+    fn named_param(&self, key: &str) -> Result<Option<&JsonValue>, ErrorVariant> {
+        match &self.params {
+            None | Some(JsonValue::Null) => Ok(None),
+            Some(JsonValue::Object(params)) => Ok(params.get(key)),
+            Some(_) => Err(ErrorVariant::InvalidParams(
+                "Expected named parameters".to_string(),
+            )),
+        }
+    }
+
+    fn deserialize_param<T: DeserializeOwned>(
+        key: &str,
+        value: &JsonValue,
+    ) -> Result<T, ErrorVariant> {
+        T::deserialize(value)
+            .map_err(|e| ErrorVariant::InvalidParams(format!("Invalid parameter '{}': {}", key, e)))
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Compact JSON of the request, or its `Debug` form if it can't be serialized
+impl fmt::Display for JrpcRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_string(self) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// Requests are equal if their members are, whatever the raw id and matched signature they carry
+impl PartialEq for JrpcRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.jsonrpc == other.jsonrpc
+            && self.method == other.method
+            && self.params == other.params
+            && self.id == other.id
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum JrpcResponseParam {
     JrpcResult(JsonValue),
     JrpcError(JrpcError),
@@ -131,17 +540,92 @@ impl JrpcResponseParam {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct JrpcResponse {
     jsonrpc: String,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
+    // A `null` result is a successful response, unlike an absent result
+    #[serde(default, deserialize_with = "deserialize_present")]
     result: Option<JsonValue>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<JrpcError>,
+    id: JsonValue,
+
+    /// Top-level members beyond the ones of the specification, used by protocol extensions
+    #[serde(flatten)]
+    extensions: Map<String, JsonValue>,
 
+    #[serde(skip)]
+    raw_id: Option<String>,
+}
+
+/// Response from a non-conforming server, which may omit the `jsonrpc` version
+#[derive(Deserialize)]
+struct JrpcLenientResponse {
+    #[serde(default = "JrpcLenientResponse::default_version")]
+    jsonrpc: String,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    result: Option<JsonValue>,
+    error: Option<JrpcError>,
     id: JsonValue,
+    #[serde(flatten)]
+    extensions: Map<String, JsonValue>,
+}
+
+impl JrpcLenientResponse {
+    fn default_version() -> String {
+        "2.0".to_string()
+    }
+}
+
+impl Serialize for JrpcResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("jsonrpc", &self.jsonrpc)?;
+
+        if let Some(result) = &self.result {
+            map.serialize_entry("result", result)?;
+        }
+
+        if let Some(error) = &self.error {
+            map.serialize_entry("error", error)?;
+        }
+
+        match &self.raw_id {
+            Some(raw_id) => {
+                let raw_id = JsonRawValue::from_string(raw_id.clone()).map_err(S::Error::custom)?;
+                map.serialize_entry("id", &raw_id)?;
+            }
+            None => map.serialize_entry("id", &self.id)?,
+        }
+
+        for (key, value) in &self.extensions {
+            if !JrpcResponse::is_standard_member(key) {
+                map.serialize_entry(key, value)?;
+            }
+        }
+
+        map.end()
+    }
+}
+
+/// Compact JSON of the response, or its `Debug` form if it can't be serialized
+impl fmt::Display for JrpcResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_string(self) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// Responses are equal if their members are, whatever the raw id they carry
+impl PartialEq for JrpcResponse {
+    fn eq(&self, other: &Self) -> bool {
+        self.jsonrpc == other.jsonrpc
+            && self.result == other.result
+            && self.error == other.error
+            && self.id == other.id
+            && self.extensions == other.extensions
+    }
 }
 
 impl JrpcResponse {
@@ -155,6 +639,8 @@ impl JrpcResponse {
             result,
             error,
             id,
+            extensions: Map::new(),
+            raw_id: None,
         };
 
         jrpc_response.validate()
@@ -176,14 +662,151 @@ impl JrpcResponse {
             JrpcResponseParam::JrpcError(e) => error = Some(e),
         }
 
-        JrpcResponse::new(result, error, id)
+        let mut jrpc_response = JrpcResponse::new(result, error, id)?;
+        jrpc_response.raw_id = request.raw_id.clone();
+        Ok(jrpc_response)
+    }
+
+    fn is_standard_member(key: &str) -> bool {
+        ["jsonrpc", "result", "error", "id"].contains(&key)
+    }
+
+    /// Add a top-level member beyond the ones of the specification, such as `meta`
+    ///
+    /// Members named after the standard ones are never serialized.
+    pub fn with_extension<T: ToString>(mut self, key: T, value: JsonValue) -> Self {
+        self.extensions.insert(key.to_string(), value);
+        self
+    }
+
+    pub(crate) fn map_error<F: FnOnce(JrpcError) -> JrpcError>(mut self, f: F) -> Self {
+        self.error = self.error.take().map(f);
+        self
+    }
+
+    /// Push this response as the outcome of the call at `index` of a batch being assembled
+    pub fn merge_into_batch(
+        self,
+        builder: &mut BatchResponseBuilder,
+        index: usize,
+    ) -> Result<(), ErrorVariant> {
+        builder.push(index, Some(self)).map(|_| ())
+    }
+
+    /// Error response echoing the id of `request`, or `null` for a notification
+    ///
+    /// Meant for transports replying on behalf of a request that couldn't be dispatched.
+    pub fn error_with_request_id(error: JrpcError, request: &JrpcRequest) -> Self {
+        JrpcResponse::from_jrpc_request(request, JrpcResponseParam::JrpcError(error))
+            .expect("An error response with the id of a valid request is always valid")
+    }
+
+    /// Error response for `error`, with the object built by `From<ErrorVariant> for JrpcError`
+    ///
+    /// An `id` that is neither a string, a number or `null` is replaced by `null`, as done for
+    /// requests whose id couldn't be read.
+    pub fn from_error_variant(error: ErrorVariant, id: JsonValue) -> Self {
+        let id = match id {
+            JsonValue::String(_) | JsonValue::Number(_) => id,
+            _ => JsonValue::Null,
+        };
+
+        JrpcResponse::new(None, Some(JrpcError::from(error)), id)
+            .expect("An error response with a valid id is always valid")
     }
 
     pub fn parse<F: ToString>(message: F) -> Result<Self, ErrorVariant> {
         let message = message.to_string();
-        serde_json::from_str::<Self>(message.as_str())
-            .map_err(|e| ErrorVariant::JsonParseError(e))
-            .and_then(|parsed| parsed.validate())
+        let value = serde_json::from_str::<JsonValue>(message.as_str())?;
+        JrpcResponse::from_value(value)
+    }
+
+    /// Build a response from an already parsed JSON value, such as an element of a batch
+    ///
+    /// Fails with `ErrorVariant::InvalidRequest` if the value is not a response object.
+    pub fn from_value(value: JsonValue) -> Result<Self, ErrorVariant> {
+        serde_json::from_value::<Self>(value)
+            .map_err(|e| ErrorVariant::InvalidRequest(e.to_string()))?
+            .validate()
+    }
+
+    /// Parse the response at the start of `message`, ignoring anything after it, and return the
+    /// byte offset where it ended
+    pub fn parse_prefix(message: &str) -> Result<(Self, usize), ErrorVariant> {
+        let (value, offset) = parse_prefix(message)?;
+        JrpcResponse::from_value(value).map(|response| (response, offset))
+    }
+
+    /// Parse a response from a server omitting the `jsonrpc` version, which then defaults to
+    /// `"2.0"`
+    ///
+    /// Every other rule of [parse](JrpcResponse::parse) still applies, including the
+    /// result/error invariants.
+    pub fn parse_lenient(message: &str) -> Result<Self, ErrorVariant> {
+        let value = serde_json::from_str::<JsonValue>(message)?;
+        let parsed = serde_json::from_value::<JrpcLenientResponse>(value)
+            .map_err(|e| ErrorVariant::InvalidRequest(e.to_string()))?;
+
+        let jrpc_response = JrpcResponse {
+            jsonrpc: parsed.jsonrpc,
+            result: parsed.result,
+            error: parsed.error,
+            id: parsed.id,
+            extensions: parsed.extensions,
+            raw_id: None,
+        };
+
+        jrpc_response.validate()
+    }
+
+    /// Parse a message straight from a byte buffer
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes(message: &[u8]) -> Result<Self, ErrorVariant> {
+        let value = serde_json::from_slice::<JsonValue>(message)?;
+        JrpcResponse::from_value(value)
+    }
+
+    /// Serialize the response into a byte buffer
+    #[cfg(feature = "bytes")]
+    pub fn to_bytes(&self) -> Result<Bytes, ErrorVariant> {
+        Ok(Bytes::from(serde_json::to_vec(self)?))
+    }
+
+    /// Serialize the response into its wire form
+    ///
+    /// Only the member present among `result` and `error` is emitted.
+    pub fn to_json_string(&self) -> Result<String, ErrorVariant> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Serialize the response indented over several lines, for debugging. Parses back to the
+    /// same response as [to_json_string](JrpcResponse::to_json_string)
+    pub fn to_json_string_pretty(&self) -> Result<String, ErrorVariant> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// The response as a JSON object, with the same members as
+    /// [to_json_string](JrpcResponse::to_json_string)
+    pub fn to_json_value(&self) -> JsonValue {
+        serde_json::to_value(self).expect("A response always serializes to a JSON object")
+    }
+
+    /// Parse the response keeping `result` as raw JSON, so only the required parts of a large
+    /// payload need to be deserialized
+    pub fn parse_lazy(message: &str) -> Result<JrpcLazyResponse<'_>, ErrorVariant> {
+        serde_json::from_str::<JrpcLazyResponse>(message)?.validate()
+    }
+
+    /// Strip the JSON-RPC framing, keeping only the result for a success, or the error object
+    /// for a failure, to embed the outcome in another protocol
+    ///
+    /// The `jsonrpc` version and the `id` are lost.
+    pub fn into_flat_value(self) -> JsonValue {
+        match (self.result, self.error) {
+            (_, Some(e)) => e.to_json_value(),
+            (Some(r), None) => r,
+            (None, None) => JsonValue::Null,
+        }
     }
 
     pub fn validate(self) -> Result<Self, ErrorVariant> {
@@ -196,7 +819,7 @@ impl JrpcResponse {
         }
 
         if self.get_result().is_none() && self.get_error().is_none() {
-            return Err(ErrorVariant::ResponseCannotContainResultAndError);
+            return Err(ErrorVariant::ResponseMustContainResultOrError);
         }
 
         // https://www.jsonrpc.org/specification#id1
@@ -210,6 +833,33 @@ impl JrpcResponse {
         Ok(self)
     }
 
+    /// Deserialize the result, if any, into `T`
+    pub fn get_result_as<T: DeserializeOwned>(&self) -> Result<Option<T>, ErrorVariant> {
+        Ok(self.result.as_ref().map(T::deserialize).transpose()?)
+    }
+
+    /// Turn the response into the typed `Result` of the call
+    ///
+    /// The result is deserialized into `T`, and the error object is mapped with
+    /// [TryFromJrpcError]. Errors that are not mapped, and failures to deserialize the result, are
+    /// converted from `ErrorVariant`, the former as `ErrorVariant::ErrorResponse`.
+    pub fn into_typed_result<T, E>(self) -> Result<T, E>
+    where
+        T: DeserializeOwned,
+        E: TryFromJrpcError + From<ErrorVariant>,
+    {
+        let response = self.validate()?;
+
+        match response.error {
+            Some(error) => Err(E::try_from_jrpc_error(error)
+                .unwrap_or_else(|error| E::from(ErrorVariant::ErrorResponse(error)))),
+            None => {
+                let result = response.result.unwrap_or(JsonValue::Null);
+                Ok(serde_json::from_value(result).map_err(ErrorVariant::from)?)
+            }
+        }
+    }
+
     pub fn get_jsonrpc(&self) -> &String {
         &self.jsonrpc
     }
@@ -218,16 +868,333 @@ impl JrpcResponse {
         &self.result
     }
 
+    /// Mutable result, e.g. to rewrite it in a post hook. The response must keep exactly one of
+    /// result and error
+    pub fn get_result_mut(&mut self) -> &mut Option<JsonValue> {
+        &mut self.result
+    }
+
     pub fn get_error(&self) -> &Option<JrpcError> {
         &self.error
     }
 
+    /// Mutable error, e.g. to redact its data. The response must keep exactly one of result and
+    /// error
+    pub fn get_error_mut(&mut self) -> &mut Option<JrpcError> {
+        &mut self.error
+    }
+
     pub fn get_id(&self) -> &JsonValue {
         &self.id
     }
+
+    /// Id of the response as an integer, or `None` for an id of another type
+    pub fn id_as_i64(&self) -> Option<i64> {
+        self.id.as_i64()
+    }
+
+    /// Id of the response as a string, or `None` for an id of another type
+    pub fn id_as_str(&self) -> Option<&str> {
+        self.id.as_str()
+    }
+
+    pub fn get_extensions(&self) -> &Map<String, JsonValue> {
+        &self.extensions
+    }
+
+    pub fn get_extensions_mut(&mut self) -> &mut Map<String, JsonValue> {
+        &mut self.extensions
+    }
 }
 
+/// Requests sent together as a batch, serialized as a JSON array
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct JrpcBatchRequest(Vec<JrpcRequest>);
+
+impl JrpcBatchRequest {
+    /// Validate the requests of a batch. An empty batch is rejected, since the specification
+    /// considers it an invalid request
+    pub fn new(requests: Vec<JrpcRequest>) -> Result<Self, ErrorVariant> {
+        if requests.is_empty() {
+            return Err(ErrorVariant::EmptyBatch);
+        }
+
+        let requests = requests
+            .into_iter()
+            .map(|request| request.validated())
+            .collect::<Result<Vec<JrpcRequest>, ErrorVariant>>()?;
+
+        Ok(JrpcBatchRequest(requests))
+    }
+
+    pub fn get_requests(&self) -> &Vec<JrpcRequest> {
+        &self.0
+    }
+
+    pub fn into_requests(self) -> Vec<JrpcRequest> {
+        self.0
+    }
+}
+
+/// Responses for the calls of a batch request, serialized as a JSON array
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JrpcBatchResponse(Vec<JrpcResponse>);
+
+impl JrpcBatchResponse {
+    pub fn new(responses: Vec<JrpcResponse>) -> Self {
+        JrpcBatchResponse(responses)
+    }
+
+    pub fn get_responses(&self) -> &Vec<JrpcResponse> {
+        &self.0
+    }
+
+    pub fn into_responses(self) -> Vec<JrpcResponse> {
+        self.0
+    }
+}
+
+/// Message received by a peer that both sends and answers calls over the same connection
+#[derive(Debug, Clone, PartialEq)]
+pub enum JrpcMessage {
+    Request(JrpcRequest),
+    Response(JrpcResponse),
+}
+
+impl JrpcMessage {
+    /// Parse a single message, told apart by the presence of `method` or of `result`/`error`
+    pub fn parse<F: ToString>(message: F) -> Result<Self, ErrorVariant> {
+        let value = serde_json::from_str::<JsonValue>(message.to_string().as_str())?;
+        JrpcMessage::from_value(value)
+    }
+
+    /// Parse a batch, whose messages may be of both kinds. An empty batch is rejected
+    pub fn parse_batch<F: ToString>(message: F) -> Result<Vec<Self>, ErrorVariant> {
+        let values = serde_json::from_str::<Vec<JsonValue>>(message.to_string().as_str())?;
+
+        if values.is_empty() {
+            return Err(ErrorVariant::EmptyBatch);
+        }
+
+        values.into_iter().map(JrpcMessage::from_value).collect()
+    }
+
+    pub fn from_value(value: JsonValue) -> Result<Self, ErrorVariant> {
+        let is_request = value.get("method").is_some();
+        let is_response = value.get("result").is_some() || value.get("error").is_some();
+
+        match (is_request, is_response) {
+            (true, false) => JrpcRequest::from_value(value).map(JrpcMessage::Request),
+            (false, true) => JrpcResponse::from_value(value).map(JrpcMessage::Response),
+            _ => Err(ErrorVariant::InvalidRequest(
+                "Expected either a request or a response".to_string(),
+            )),
+        }
+    }
+}
+
+/// Order of the responses assembled by a [BatchResponseBuilder]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchOrder {
+    /// Order of the requests in the batch
+    Request,
+    /// Order in which the responses were pushed
+    Completion,
+}
+
+/// Fluent construction of a [JrpcRequest], setting only the members needed
+///
+/// Without an [id](JrpcRequestBuilder::id), the request is a notification.
+#[derive(Debug, Clone)]
+pub struct JrpcRequestBuilder {
+    method: String,
+    params: Option<JsonValue>,
+    id: Option<JsonValue>,
+}
+
+impl JrpcRequestBuilder {
+    pub fn new<T: ToString>(method: T) -> Self {
+        JrpcRequestBuilder {
+            method: method.to_string(),
+            params: None,
+            id: None,
+        }
+    }
+
+    pub fn method<T: ToString>(mut self, method: T) -> Self {
+        self.method = method.to_string();
+        self
+    }
+
+    pub fn params<T: Into<JsonValue>>(mut self, params: T) -> Self {
+        self.params = Some(params.into());
+        self
+    }
+
+    pub fn id<T: Into<JsonValue>>(mut self, id: T) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Clear the id, so the request is sent as a notification
+    pub fn notification(mut self) -> Self {
+        self.id = None;
+        self
+    }
+
+    /// Build the request, validated as with [new](JrpcRequest::new)
+    pub fn build(self) -> Result<JrpcRequest, ErrorVariant> {
+        JrpcRequest::new(self.method, self.params, self.id)
+    }
+}
+
+/// Assemble the responses of a batch whose calls complete in any order
+///
+/// Every call of the batch, identified by its index, must be pushed exactly once, with `None`
+/// for a notification, before building the batch.
 #[derive(Debug, Clone)]
+pub struct BatchResponseBuilder {
+    order: BatchOrder,
+    pushed: Vec<bool>,
+    responses: Vec<(usize, JrpcResponse)>,
+}
+
+impl BatchResponseBuilder {
+    pub fn new(len: usize, order: BatchOrder) -> Self {
+        BatchResponseBuilder {
+            order,
+            pushed: vec![false; len],
+            responses: Vec::with_capacity(len),
+        }
+    }
+
+    /// Record the outcome of the call at `index`. Fails with `ErrorVariant::InvalidBatchSlot` if
+    /// the index is out of the batch or was already pushed
+    pub fn push(
+        &mut self,
+        index: usize,
+        response: Option<JrpcResponse>,
+    ) -> Result<&mut Self, ErrorVariant> {
+        match self.pushed.get_mut(index) {
+            Some(pushed) if !*pushed => *pushed = true,
+            _ => return Err(ErrorVariant::InvalidBatchSlot(index)),
+        }
+
+        if let Some(response) = response {
+            self.responses.push((index, response));
+        }
+
+        Ok(self)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pushed.iter().all(|pushed| *pushed)
+    }
+
+    /// Responses of the batch in the chosen order, empty if every call was a notification. Fails
+    /// with `ErrorVariant::InvalidBatchSlot` for the first call that wasn't pushed
+    pub fn build(mut self) -> Result<Vec<JrpcResponse>, ErrorVariant> {
+        if let Some(missing) = self.pushed.iter().position(|pushed| !*pushed) {
+            return Err(ErrorVariant::InvalidBatchSlot(missing));
+        }
+
+        if self.order == BatchOrder::Request {
+            self.responses.sort_by_key(|(index, _)| *index);
+        }
+
+        Ok(self
+            .responses
+            .into_iter()
+            .map(|(_, response)| response)
+            .collect())
+    }
+}
+
+/// Reply to a message that may be a batch
+///
+/// https://www.jsonrpc.org/specification#batch
+///
+/// A message that can't be parsed at all, or that is an empty array, is answered with a single
+/// error object. Otherwise, each call of the batch has its own response.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum JrpcBatchOutcome {
+    Single(JrpcResponse),
+    Batch(JrpcBatchResponse),
+}
+
+/// Response borrowing its `result` from the original message, as produced by
+/// [parse_lazy](JrpcResponse::parse_lazy)
+#[derive(Deserialize, Debug, Clone)]
+pub struct JrpcLazyResponse<'m> {
+    jsonrpc: String,
+
+    #[serde(borrow, default, deserialize_with = "deserialize_present")]
+    result: Option<&'m JsonRawValue>,
+
+    error: Option<JrpcError>,
+
+    id: JsonValue,
+}
+
+impl<'m> JrpcLazyResponse<'m> {
+    pub fn validate(self) -> Result<Self, ErrorVariant> {
+        if self.get_jsonrpc() != "2.0" {
+            return Err(ErrorVariant::InvalidJsonRpcVersion);
+        }
+
+        if self.get_raw_result().is_some() && self.get_error().is_some() {
+            return Err(ErrorVariant::ResponseCannotContainResultAndError);
+        }
+
+        if self.get_raw_result().is_none() && self.get_error().is_none() {
+            return Err(ErrorVariant::ResponseMustContainResultOrError);
+        }
+
+        // https://www.jsonrpc.org/specification#id1
+        match self.get_id() {
+            JsonValue::String(_) => (),
+            JsonValue::Number(_) => (),
+            JsonValue::Null => (),
+            _ => return Err(ErrorVariant::InvalidJsonRpcId),
+        }
+
+        Ok(self)
+    }
+
+    /// Deserialize the raw `result` into `T`. The target can be a partial view of the payload, so
+    /// unused fields are skipped without being materialized
+    pub fn get_result_as<T: Deserialize<'m>>(&self) -> Result<Option<T>, ErrorVariant> {
+        Ok(self
+            .result
+            .map(|raw| serde_json::from_str::<T>(raw.get()))
+            .transpose()?)
+    }
+
+    /// Fully deserialize the raw `result`, producing a regular response
+    pub fn into_response(self) -> Result<JrpcResponse, ErrorVariant> {
+        let result = self.get_result_as::<JsonValue>()?;
+        JrpcResponse::new(result, self.error, self.id)
+    }
+
+    pub fn get_jsonrpc(&self) -> &String {
+        &self.jsonrpc
+    }
+
+    pub fn get_raw_result(&self) -> Option<&'m JsonRawValue> {
+        self.result
+    }
+
+    pub fn get_error(&self) -> &Option<JrpcError> {
+        &self.error
+    }
+
+    pub fn get_id(&self) -> &JsonValue {
+        &self.id
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JrpcErrorEnum {
     ParseError = -32700,
     InvalidRequest = -32600,
@@ -250,7 +1217,7 @@ impl From<i32> for JrpcErrorEnum {
             JrpcErrorEnum::InvalidParams
         } else if code == -32603 {
             JrpcErrorEnum::InternalError
-        } else if code >= -32099 && code <= -32000 {
+        } else if (-32099..=-32000).contains(&code) {
             JrpcErrorEnum::ServerError
         } else {
             JrpcErrorEnum::Other
@@ -272,7 +1239,7 @@ impl From<JrpcErrorEnum> for i32 {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct JrpcError {
     code: i32,
     message: String,
@@ -289,14 +1256,108 @@ impl JrpcError {
         }
     }
 
+    /// Build an error carrying a structured `data` payload, e.g. the fields of invalid params
+    pub fn with_data<T: ToString>(code: i32, message: T, data: JsonValue) -> Self {
+        JrpcError::new(code, message, Some(data))
+    }
+
+    /// Build an error, rejecting the codes reserved by the specification, `-32768` to `-32000`,
+    /// that are not predefined
+    ///
+    /// Predefined codes are the ones of [JrpcErrorEnum], including the range `-32099` to `-32000`
+    /// left to implementation-defined server errors.
+    pub fn new_checked<T: ToString>(
+        code: i32,
+        message: T,
+        data: Option<JsonValue>,
+    ) -> Result<Self, ErrorVariant> {
+        match JrpcErrorEnum::from(code) {
+            JrpcErrorEnum::Other if (-32768..=-32000).contains(&code) => {
+                Err(ErrorVariant::InvalidErrorCode(code))
+            }
+            _ => Ok(JrpcError::new(code, message, data)),
+        }
+    }
+
+    /// Build an error with an application defined code, rejecting the codes reserved by the
+    /// specification, `-32768` to `-32000`
+    ///
+    /// https://www.jsonrpc.org/specification#error_object
+    pub fn application_error<T: ToString>(
+        code: i32,
+        message: T,
+        data: Option<JsonValue>,
+    ) -> Result<Self, ErrorVariant> {
+        if (-32768..=-32000).contains(&code) {
+            return Err(ErrorVariant::InvalidErrorCode(code));
+        }
+
+        Ok(JrpcError::new(code, message, data))
+    }
+
+    /// Capture a backtrace of the current thread into `data`, as `{"backtrace": "..."}`
+    ///
+    /// Meant to track down internal errors during development, so release builds leave the
+    /// error untouched to avoid leaking server internals. Existing `data` is kept under the
+    /// `data` key.
+    pub fn with_backtrace(self) -> Self {
+        if !cfg!(debug_assertions) {
+            return self;
+        }
+
+        self.attach_backtrace(Backtrace::force_capture().to_string())
+    }
+
+    pub(crate) fn attach_backtrace(self, backtrace: String) -> Self {
+        let data = match self.data {
+            Some(data) => serde_json::json!({ "backtrace": backtrace, "data": data }),
+            None => serde_json::json!({ "backtrace": backtrace }),
+        };
+
+        JrpcError::new(self.code, self.message, Some(data))
+    }
+
+    /// Add the category of the code in `data`, as the name of its [JrpcErrorEnum], such as
+    /// `{"category": "InvalidParams"}`
+    ///
+    /// The category is added to an existing object, and any other existing `data` is kept under
+    /// the `data` key.
+    pub fn with_category(self) -> Self {
+        let category = JsonValue::from(format!("{:?}", JrpcErrorEnum::from(self.code)));
+        let data = match self.data {
+            Some(JsonValue::Object(mut data)) => {
+                data.insert("category".to_string(), category);
+                JsonValue::Object(data)
+            }
+            Some(data) => serde_json::json!({ "category": category, "data": data }),
+            None => serde_json::json!({ "category": category }),
+        };
+
+        JrpcError::new(self.code, self.message, Some(data))
+    }
+
+    /// Aggregate several failures into one error, with the nested errors as a JSON array in `data`
+    pub fn with_nested_errors<T: ToString>(code: i32, message: T, errors: Vec<JrpcError>) -> Self {
+        let errors = errors.iter().map(JrpcError::to_json_value).collect();
+
+        JrpcError::new(code, message, Some(JsonValue::Array(errors)))
+    }
+
     pub fn parse<F: ToString>(message: F) -> Result<Self, ErrorVariant> {
         let message = message.to_string();
-        let parsed: JrpcError =
-            serde_json::from_str(message.as_str()).map_err(|e| ErrorVariant::JsonParseError(e))?;
+        let parsed: JrpcError = serde_json::from_str(message.as_str())?;
 
         Ok(parsed)
     }
 
+    pub fn to_json_value(&self) -> JsonValue {
+        serde_json::json!({
+            "code": self.code,
+            "message": self.message,
+            "data": self.data,
+        })
+    }
+
     pub fn get_code(&self) -> &i32 {
         &self.code
     }
@@ -308,6 +1369,35 @@ impl JrpcError {
     pub fn get_data(&self) -> &Option<JsonValue> {
         &self.data
     }
+
+    pub fn get_data_mut(&mut self) -> &mut Option<JsonValue> {
+        &mut self.data
+    }
+
+    /// Replace the `data` of the error
+    pub fn set_data(&mut self, data: JsonValue) {
+        self.data = Some(data);
+    }
+
+    /// Level at which to log this error, by who is likely at fault
+    ///
+    /// Internal errors are logged as `Error`. Other errors reserved by the specification, caused
+    /// by the client or by the server being busy, are logged as `Warn`. Application defined errors
+    /// are regular outcomes of their methods, logged as `Info`.
+    pub fn log_level(&self) -> log::Level {
+        match self.code {
+            -32603 => log::Level::Error,
+            -32768..=-32000 => log::Level::Warn,
+            _ => log::Level::Info,
+        }
+    }
+}
+
+/// Conversion of error objects into an application error type, see
+/// [into_typed_result](JrpcResponse::into_typed_result)
+pub trait TryFromJrpcError: Sized {
+    /// Map `error`, usually by its code, or give it back if it isn't recognized
+    fn try_from_jrpc_error(error: JrpcError) -> Result<Self, JrpcError>;
 }
 
 impl From<JrpcErrorEnum> for JrpcError {
@@ -328,6 +1418,15 @@ impl From<JrpcErrorEnum> for JrpcError {
     }
 }
 
+impl JrpcErrorEnum {
+    /// Predefined error of this kind, with `data` attached
+    pub fn into_error_with_data(self, data: JsonValue) -> JrpcError {
+        let mut error = JrpcError::from(self);
+        error.set_data(data);
+        error
+    }
+}
+
 impl From<i32> for JrpcError {
     fn from(error_code: i32) -> Self {
         let jrpc_error_enum = JrpcErrorEnum::from(error_code);
@@ -339,11 +1438,32 @@ impl From<ErrorVariant> for JrpcError {
     fn from(error_variant: ErrorVariant) -> Self {
         match error_variant {
             ErrorVariant::MethodSignatureNotFound(_) => JrpcError::from(-32601),
+            ErrorVariant::MethodDisabled(_) => JrpcError::from(-32601),
             ErrorVariant::JsonParseError(_) => JrpcError::from(-32700),
+            ErrorVariant::InvalidRequest(_) => JrpcError::from(-32600),
             ErrorVariant::InvalidJsonRpcVersion => JrpcError::from(-32600),
             ErrorVariant::InvalidJsonRpcId => JrpcError::from(-32600),
+            ErrorVariant::DuplicateRequestId(_) => JrpcError::from(-32600),
+            ErrorVariant::RateLimited(_, retry_after) => JrpcError::new(
+                i32::from(JrpcErrorEnum::ServerError),
+                "Rate limit exceeded",
+                Some(serde_json::json!({ "retry_after_ms": retry_after.as_millis() as u64 })),
+            ),
+            ErrorVariant::Timeout(_, timeout) => JrpcError::new(
+                i32::from(JrpcErrorEnum::ServerError),
+                "Request timed out",
+                Some(serde_json::json!({ "timeout_ms": timeout.as_millis() as u64 })),
+            ),
+            ErrorVariant::EmptyBatch => JrpcError::from(-32600),
+            ErrorVariant::MessageTooLarge(_) => JrpcError::from(-32600),
             ErrorVariant::ResponseCannotContainResultAndError => JrpcError::from(-32600),
             ErrorVariant::ResponseMustContainResultOrError => JrpcError::from(-32600),
+            ErrorVariant::InvalidParams(_) => JrpcError::from(-32602),
+            ErrorVariant::ErrorResponse(e) => e,
+            ErrorVariant::MethodError(_, e) => JrpcError::from(*e),
+            ErrorVariant::WithBacktrace(e, backtrace) => {
+                JrpcError::from(*e).attach_backtrace(backtrace)
+            }
             _ => JrpcError::from(-32603),
         }
     }
@@ -0,0 +1,83 @@
+use crate::ErrorVariant;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Fixed-window call limits, per method signature
+pub(crate) struct RateLimits {
+    limits: Mutex<HashMap<String, RateLimit>>,
+}
+
+struct RateLimit {
+    max_calls: u32,
+    window: Duration,
+    window_start: Instant,
+    calls: u32,
+}
+
+impl RateLimits {
+    pub(crate) fn new() -> Self {
+        RateLimits {
+            limits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn set(
+        &self,
+        signature: String,
+        max_calls: u32,
+        window: Duration,
+    ) -> Result<(), ErrorVariant> {
+        let limit = RateLimit {
+            max_calls,
+            window,
+            window_start: Instant::now(),
+            calls: 0,
+        };
+
+        self.limits
+            .lock()
+            .map(|mut limits| {
+                limits.insert(signature, limit);
+            })
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+    }
+
+    pub(crate) fn remove(&self, signature: &str) -> Result<bool, ErrorVariant> {
+        self.limits
+            .lock()
+            .map(|mut limits| limits.remove(signature).is_some())
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+    }
+
+    /// Count a call to `signature`, failing with the time left in the window if the limit is hit
+    pub(crate) fn acquire(&self, signature: &str) -> Result<(), ErrorVariant> {
+        let mut limits = self
+            .limits
+            .lock()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)?;
+
+        let limit = match limits.get_mut(signature) {
+            Some(l) => l,
+            None => return Ok(()),
+        };
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(limit.window_start);
+        if elapsed >= limit.window {
+            limit.window_start = now;
+            limit.calls = 0;
+        }
+
+        if limit.calls < limit.max_calls {
+            limit.calls += 1;
+            return Ok(());
+        }
+
+        let retry_after = limit.window - now.duration_since(limit.window_start);
+        Err(ErrorVariant::RateLimited(
+            signature.to_string(),
+            retry_after,
+        ))
+    }
+}
@@ -0,0 +1,155 @@
+use crate::futures::future;
+use crate::futures::prelude::*;
+use crate::{ErrorVariant, JrpcBatchOutcome, JrpcHandler, JrpcRequest, JrpcResponse};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Dispatch state of a single client connection
+///
+/// Connection-oriented transports should create one session per connection, so requests can be
+/// checked against the other requests of the same client. The transports of this crate, such as
+/// [serve_duplex](crate::transport::serve_duplex), do so.
+pub struct JrpcSession<'a> {
+    handler: JrpcHandler<'a>,
+    unique_ids: Arc<AtomicBool>,
+    in_flight: InFlightIds,
+}
+
+impl<'a> Clone for JrpcSession<'a> {
+    fn clone(&self) -> Self {
+        let handler = self.handler.clone();
+        let unique_ids = self.unique_ids.clone();
+        let in_flight = self.in_flight.clone();
+        JrpcSession {
+            handler,
+            unique_ids,
+            in_flight,
+        }
+    }
+}
+
+impl<'a> JrpcSession<'a> {
+    /// Start a session of `handler`, enforcing unique ids if the handler is set to, see
+    /// [set_enforce_unique_ids](JrpcHandler::set_enforce_unique_ids)
+    pub fn new(handler: JrpcHandler<'a>) -> Result<Self, ErrorVariant> {
+        let unique_ids = Arc::new(AtomicBool::new(handler.enforces_unique_ids()?));
+        let in_flight = InFlightIds::default();
+        let session = JrpcSession {
+            handler,
+            unique_ids,
+            in_flight,
+        };
+        Ok(session)
+    }
+
+    /// Reject requests reusing the id of a request of this session that is still in flight, with
+    /// `ErrorVariant::DuplicateRequestId`
+    ///
+    /// An id is released as soon as the future of its request resolves, fails or is dropped, so
+    /// it can be reused by later requests. In a batch, the duplicated request is answered with a
+    /// `-32600` error, while the others are dispatched.
+    pub fn set_enforce_unique_ids(&self, enforce: bool) -> Result<&Self, ErrorVariant> {
+        self.unique_ids.store(enforce, Ordering::SeqCst);
+        Ok(self)
+    }
+
+    pub fn get_handler(&self) -> &JrpcHandler<'a> {
+        &self.handler
+    }
+
+    pub fn handle_message<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let message = match self.handler.preprocess(message.as_ref())? {
+            Ok(m) => m,
+            Err(response) => return Ok(Box::new(future::ok(Some(response)))),
+        };
+
+        let request = self.handler.parse_request(&message)?;
+        self.handle_request(request)
+    }
+
+    pub fn handle_request(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let guard = match self.tracked_ids() {
+            Some(in_flight) => in_flight.track(&request)?,
+            None => None,
+        };
+
+        let future = self.handler.handle_request(request)?;
+        let future = future.then(move |result| {
+            drop(guard);
+            result
+        });
+
+        Ok(Box::new(future))
+    }
+
+    /// Parse and dispatch a single or batch message as
+    /// [handle_batch_message](JrpcHandler::handle_batch_message), checking the ids of its requests
+    /// against the other requests of the session
+    pub fn handle_batch_message<T: AsRef<str>>(
+        &self,
+        message: T,
+    ) -> Result<
+        Box<dyn 'a + Future<Item = Option<JrpcBatchOutcome>, Error = ErrorVariant>>,
+        ErrorVariant,
+    > {
+        self.handler
+            .handle_batch(message.as_ref(), None, self.tracked_ids())
+    }
+
+    fn tracked_ids(&self) -> Option<InFlightIds> {
+        Some(self.in_flight.clone()).filter(|_| self.unique_ids.load(Ordering::SeqCst))
+    }
+}
+
+/// Ids of the requests of a session that are still in flight
+#[derive(Clone, Default)]
+pub(crate) struct InFlightIds(Arc<Mutex<HashSet<String>>>);
+
+impl InFlightIds {
+    /// Track the id of `request` until the returned guard is dropped, failing with
+    /// `ErrorVariant::DuplicateRequestId` if it is already in flight. Notifications are not tracked
+    pub(crate) fn track(&self, request: &JrpcRequest) -> Result<Option<InFlightId>, ErrorVariant> {
+        let id = match request.get_id() {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let key = serde_json::to_string(id)?;
+        self.0
+            .lock()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+            .and_then(|mut in_flight| {
+                if in_flight.insert(key.clone()) {
+                    Ok(Some(InFlightId {
+                        in_flight: self.0.clone(),
+                        key,
+                    }))
+                } else {
+                    Err(ErrorVariant::DuplicateRequestId(id.clone()))
+                }
+            })
+    }
+}
+
+/// Releases an in-flight id when dropped
+pub(crate) struct InFlightId {
+    in_flight: Arc<Mutex<HashSet<String>>>,
+    key: String,
+}
+
+impl Drop for InFlightId {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.remove(&self.key);
+        }
+    }
+}
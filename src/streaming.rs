@@ -0,0 +1,176 @@
+use crate::futures::prelude::*;
+use crate::futures::sync::{mpsc, oneshot};
+use crate::method::ParamsStream;
+use crate::{ErrorVariant, JrpcResponse, JsonValue};
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::Map;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{BufReader, Read};
+use std::thread;
+
+/// Parsed elements buffered ahead of the method before the parser blocks
+const PARAMS_BUFFER: usize = 16;
+
+pub(crate) type Members = Map<String, JsonValue>;
+
+/// First outcome of parsing a message
+pub(crate) enum Head {
+    /// A streaming method was called. The other members follow once the message is read
+    Streaming(
+        String,
+        ParamsStream,
+        oneshot::Receiver<Result<Members, ErrorVariant>>,
+    ),
+    /// The message was read in full, without streaming its params
+    Complete(Result<Members, ErrorVariant>),
+}
+
+struct Senders {
+    head: Option<oneshot::Sender<Head>>,
+    tail: Option<oneshot::Sender<Result<Members, ErrorVariant>>>,
+}
+
+/// Parse a request read from `reader` on its own thread, streaming the params of the methods in
+/// `streaming`
+pub(crate) fn parse<R: Read + Send + 'static>(
+    reader: R,
+    streaming: HashSet<String>,
+) -> impl Future<Item = Head, Error = ErrorVariant> {
+    let (head, receiver) = oneshot::channel();
+
+    thread::spawn(move || {
+        let mut senders = Senders {
+            head: Some(head),
+            tail: None,
+        };
+
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+        let members = deserializer
+            .deserialize_map(MessageVisitor {
+                streaming: &streaming,
+                senders: &mut senders,
+            })
+            .and_then(|members| deserializer.end().map(|_| members))
+            .map_err(ErrorVariant::from);
+
+        // Nobody waits for the outcome if the dispatch was dropped
+        match (senders.tail.take(), senders.head.take()) {
+            (Some(tail), _) => {
+                let _ = tail.send(members);
+            }
+            (None, Some(head)) => {
+                let _ = head.send(Head::Complete(members));
+            }
+            (None, None) => (),
+        }
+    });
+
+    receiver.map_err(|_| ErrorVariant::InternalError)
+}
+
+/// Response to a streamed request, once the method resolved to `result`
+pub(crate) fn respond(
+    result: JsonValue,
+    members: Members,
+) -> Result<Option<JrpcResponse>, ErrorVariant> {
+    match members.get("jsonrpc") {
+        Some(JsonValue::String(v)) if v == "2.0" => (),
+        _ => return Err(ErrorVariant::InvalidJsonRpcVersion),
+    }
+
+    // https://www.jsonrpc.org/specification#id1
+    match members.get("id") {
+        None => Ok(None),
+        Some(id @ JsonValue::String(_))
+        | Some(id @ JsonValue::Number(_))
+        | Some(id @ JsonValue::Null) => JrpcResponse::new(Some(result), None, id.clone()).map(Some),
+        Some(_) => Err(ErrorVariant::InvalidJsonRpcId),
+    }
+}
+
+struct MessageVisitor<'s> {
+    streaming: &'s HashSet<String>,
+    senders: &'s mut Senders,
+}
+
+impl<'s, 'de> Visitor<'de> for MessageVisitor<'s> {
+    type Value = Members;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a JSON-RPC request object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Members, A::Error> {
+        let mut members = Map::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let method = match members.get("method") {
+                Some(JsonValue::String(m)) if key == "params" && self.streaming.contains(m) => {
+                    Some(m.clone())
+                }
+                _ => None,
+            };
+
+            match (method, self.senders.head.take()) {
+                (Some(method), Some(head)) => {
+                    let (sender, receiver) = mpsc::channel(PARAMS_BUFFER);
+                    let (tail, tail_receiver) = oneshot::channel();
+                    let params: ParamsStream = Box::new(receiver.then(|item| match item {
+                        Ok(item) => item,
+                        Err(_) => Err(ErrorVariant::InternalError),
+                    }));
+
+                    self.senders.tail = Some(tail);
+                    let _ = head.send(Head::Streaming(method, params, tail_receiver));
+                    map.next_value_seed(StreamParams(sender))?;
+                }
+                (_, head) => {
+                    self.senders.head = head;
+                    let value = map.next_value::<JsonValue>()?;
+                    members.insert(key, value);
+                }
+            }
+        }
+
+        Ok(members)
+    }
+}
+
+type ParamsSender = mpsc::Sender<Result<JsonValue, ErrorVariant>>;
+
+/// Sends the elements of the params array to the method as they are parsed
+struct StreamParams(ParamsSender);
+
+impl<'de> DeserializeSeed<'de> for StreamParams {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let sender = self.0.clone();
+
+        deserializer.deserialize_seq(self).inspect_err(|e| {
+            let _ = sender
+                .send(Err(ErrorVariant::InvalidParams(e.to_string())))
+                .wait();
+        })
+    }
+}
+
+impl<'de> Visitor<'de> for StreamParams {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of params")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        // Once the method drops the stream, the remaining elements are parsed and discarded
+        let mut sender = Some(self.0);
+
+        while let Some(element) = seq.next_element::<JsonValue>()? {
+            sender = sender.and_then(|s| s.send(Ok(element)).wait().ok());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::futures::prelude::*;
+use crate::futures::sync::oneshot;
+use crate::ErrorVariant;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Dispatch timeouts, per method signature, with a default for the others
+#[derive(Clone, Default)]
+pub(crate) struct Timeouts {
+    default: Option<Duration>,
+    methods: HashMap<String, Duration>,
+}
+
+impl Timeouts {
+    pub(crate) fn set_default(&mut self, timeout: Option<Duration>) {
+        self.default = timeout;
+    }
+
+    pub(crate) fn set(&mut self, signature: String, timeout: Duration) {
+        self.methods.insert(signature, timeout);
+    }
+
+    pub(crate) fn remove(&mut self, signature: &str) -> bool {
+        self.methods.remove(signature).is_some()
+    }
+
+    /// Timeout of `signature`, falling back to the default
+    pub(crate) fn get(&self, signature: &str) -> Option<Duration> {
+        self.methods.get(signature).cloned().or(self.default)
+    }
+}
+
+/// Future resolving once `timeout` elapsed
+///
+/// Deadlines are kept by a single timer thread shared by the whole process, started on first use,
+/// so no runtime is required. A deadline dropped before it elapses, e.g. because the guarded
+/// method resolved first, is removed from the timer right away.
+///
+/// Fails with `ErrorVariant::IoError` if the timer thread can't be started. The future fails with
+/// `ErrorVariant::RwLockPoisoned` if the timer stops before the deadline.
+pub(crate) fn deadline(timeout: Duration) -> Result<Deadline, ErrorVariant> {
+    let timer = timer()?;
+    let (sender, receiver) = oneshot::channel();
+    let key = (
+        Instant::now() + timeout,
+        timer.next_key.fetch_add(1, AtomicOrdering::Relaxed),
+    );
+
+    timer
+        .deadlines
+        .lock()
+        .map(|mut deadlines| {
+            deadlines.insert(key, sender);
+        })
+        .map_err(|_| ErrorVariant::RwLockPoisoned)?;
+    timer.condvar.notify_one();
+
+    Ok(Deadline {
+        timer,
+        key,
+        receiver,
+    })
+}
+
+pub(crate) struct Deadline {
+    timer: Arc<Timer>,
+    key: (Instant, u64),
+    receiver: oneshot::Receiver<()>,
+}
+
+impl Future for Deadline {
+    type Item = ();
+    type Error = ErrorVariant;
+
+    fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+        self.receiver
+            .poll()
+            .map_err(|_| ErrorVariant::RwLockPoisoned)
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        if let Ok(mut deadlines) = self.timer.deadlines.lock() {
+            deadlines.remove(&self.key);
+        }
+    }
+}
+
+struct Timer {
+    // Keyed by expiry, then by a sequence number telling apart deadlines expiring together
+    deadlines: Mutex<BTreeMap<(Instant, u64), oneshot::Sender<()>>>,
+    condvar: Condvar,
+    next_key: AtomicU64,
+}
+
+fn timer() -> Result<Arc<Timer>, ErrorVariant> {
+    static TIMER: Mutex<Option<Arc<Timer>>> = Mutex::new(None);
+
+    let mut global = TIMER.lock().map_err(|_| ErrorVariant::RwLockPoisoned)?;
+
+    if let Some(timer) = global.as_ref() {
+        return Ok(timer.clone());
+    }
+
+    let timer = Arc::new(Timer {
+        deadlines: Mutex::new(BTreeMap::new()),
+        condvar: Condvar::new(),
+        next_key: AtomicU64::new(0),
+    });
+
+    let running = timer.clone();
+    thread::Builder::new()
+        .name("futures-jsonrpc-timer".to_string())
+        .spawn(move || run_timer(&running))?;
+
+    global.replace(timer.clone());
+    Ok(timer)
+}
+
+fn run_timer(timer: &Timer) {
+    let mut deadlines = match timer.deadlines.lock() {
+        Ok(d) => d,
+        Err(_) => return stop_timer(timer),
+    };
+
+    loop {
+        let now = Instant::now();
+        let wait = match deadlines.keys().next().cloned() {
+            Some(key) if key.0 <= now => {
+                if let Some(sender) = deadlines.remove(&key) {
+                    // The receiver is gone if the method resolved first
+                    let _ = sender.send(());
+                }
+                continue;
+            }
+            Some(key) => Some(key.0 - now),
+            None => None,
+        };
+
+        deadlines = match wait {
+            Some(wait) => match timer.condvar.wait_timeout(deadlines, wait) {
+                Ok((d, _)) => d,
+                Err(_) => return stop_timer(timer),
+            },
+            None => match timer.condvar.wait(deadlines) {
+                Ok(d) => d,
+                Err(_) => return stop_timer(timer),
+            },
+        };
+    }
+}
+
+/// Fail every pending deadline, so no future waits for a timer that is gone
+fn stop_timer(timer: &Timer) {
+    error!("The timer thread stopped on a poisoned lock");
+
+    timer
+        .deadlines
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
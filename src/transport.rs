@@ -0,0 +1,171 @@
+//! Newline delimited JSON-RPC over TCP, on top of tokio
+use crate::futures::prelude::*;
+use crate::futures::{future, stream};
+use crate::{ErrorVariant, JrpcHandler, JrpcRequest, JrpcSession};
+use bytes::{BufMut, BytesMut};
+use std::io::{Error as IoError, ErrorKind};
+use tokio::codec::{Decoder, Encoder};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::current_thread;
+
+/// Codec framing one JSON-RPC message per line
+///
+/// Frames may be split across several reads, or several frames coalesced in a single read. A
+/// trailing `\r` is stripped, and blank lines are skipped. Outgoing messages must not contain raw
+/// newlines, which holds for any message serialized by `serde_json` without pretty printing.
+#[derive(Debug, Clone, Default)]
+pub struct LineDelimitedCodec {
+    // Bytes of the buffer already searched for a newline
+    next_index: usize,
+}
+
+impl LineDelimitedCodec {
+    pub fn new() -> Self {
+        LineDelimitedCodec { next_index: 0 }
+    }
+
+    fn into_message(line: BytesMut) -> Result<Option<String>, ErrorVariant> {
+        let mut line = &line[..];
+        while let Some((b'\n', rest)) | Some((b'\r', rest)) = line.split_last() {
+            line = rest;
+        }
+
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+
+        String::from_utf8(line.to_vec())
+            .map(Some)
+            .map_err(|e| ErrorVariant::IoError(IoError::new(ErrorKind::InvalidData, e)))
+    }
+}
+
+impl Decoder for LineDelimitedCodec {
+    type Item = String;
+    type Error = ErrorVariant;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<String>, ErrorVariant> {
+        loop {
+            let newline = buf[self.next_index..].iter().position(|b| *b == b'\n');
+
+            match newline {
+                Some(offset) => {
+                    let line = buf.split_to(self.next_index + offset + 1);
+                    self.next_index = 0;
+
+                    if let Some(message) = LineDelimitedCodec::into_message(line)? {
+                        return Ok(Some(message));
+                    }
+                }
+                None => {
+                    self.next_index = buf.len();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<String>, ErrorVariant> {
+        if let Some(message) = self.decode(buf)? {
+            return Ok(Some(message));
+        }
+
+        // The last message may not be terminated by a newline
+        self.next_index = 0;
+        let len = buf.len();
+        LineDelimitedCodec::into_message(buf.split_to(len))
+    }
+}
+
+impl Encoder for LineDelimitedCodec {
+    type Item = String;
+    type Error = ErrorVariant;
+
+    fn encode(&mut self, message: String, buf: &mut BytesMut) -> Result<(), ErrorVariant> {
+        buf.reserve(message.len() + 1);
+        buf.put(message);
+        buf.put_u8(b'\n');
+        Ok(())
+    }
+}
+
+/// Serve every connection accepted by `listener` with `handler`, one message per line
+///
+/// Both single and batch requests are accepted, and the replies of a connection are sent in the
+/// order of its requests. Each connection is served with its own [JrpcSession], as by
+/// [serve_duplex]. Since method futures are not required to be `Send`, connections are spawned on
+/// the current thread, so the returned future must run on a tokio `current_thread` runtime.
+pub fn serve_tcp(
+    listener: TcpListener,
+    handler: JrpcHandler<'static>,
+) -> impl Future<Item = (), Error = ErrorVariant> {
+    listener
+        .incoming()
+        .map_err(ErrorVariant::IoError)
+        .for_each(move |socket| {
+            let peer = socket.peer_addr().ok();
+            let connection = serve_connection(socket, handler.clone()).map_err(move |e| {
+                warn!("Connection with {:?} failed: {}", peer, e);
+            });
+
+            current_thread::spawn(connection);
+            Ok(())
+        })
+}
+
+fn serve_connection(
+    socket: TcpStream,
+    handler: JrpcHandler<'static>,
+) -> impl Future<Item = (), Error = ErrorVariant> {
+    let (sink, stream) = LineDelimitedCodec::new().framed(socket).split();
+    serve_duplex(handler, stream, sink, stream::empty())
+}
+
+/// Serve the messages of `incoming` with `handler`, writing the serialized replies to `sink`
+///
+/// Messages pushed to `notifications` by the server are serialized and written to `sink` as well,
+/// interleaved with the replies. Replies are written in the order of their requests, and the
+/// returned future resolves once `incoming` ends and every reply was flushed, regardless of
+/// `notifications`.
+///
+/// The messages are dispatched through a [JrpcSession] of `handler`, so they are checked against
+/// the other requests of the same connection, see
+/// [set_enforce_unique_ids](JrpcHandler::set_enforce_unique_ids).
+///
+/// This is the glue for any message based transport, such as WebSockets or in-memory channels.
+pub fn serve_duplex<'a, I, S, N>(
+    handler: JrpcHandler<'a>,
+    incoming: I,
+    sink: S,
+    notifications: N,
+) -> impl 'a + Future<Item = (), Error = ErrorVariant>
+where
+    I: 'a + Stream<Item = String, Error = ErrorVariant>,
+    S: 'a + Sink<SinkItem = String, SinkError = ErrorVariant>,
+    N: 'a + Stream<Item = JrpcRequest, Error = ErrorVariant>,
+{
+    future::result(JrpcSession::new(handler)).and_then(move |session| {
+        // Replies end with `None`, closing the outgoing stream even if notifications are still open
+        let replies = incoming
+            .and_then(move |message| {
+                future::result(session.handle_batch_message(message)).flatten()
+            })
+            .filter_map(|outcome| outcome)
+            .and_then(|outcome| serde_json::to_string(&outcome).map_err(ErrorVariant::from))
+            .map(Some)
+            .chain(stream::once(Ok(None)));
+
+        let notifications = notifications
+            .and_then(|notification| {
+                serde_json::to_string(&notification).map_err(ErrorVariant::from)
+            })
+            .map(Some);
+
+        let outgoing = replies
+            .select(notifications)
+            .take_while(|message| future::ok(message.is_some()))
+            .filter_map(|message| message);
+
+        sink.send_all(outgoing).map(|_| ())
+    })
+}
@@ -0,0 +1,55 @@
+use futures_jsonrpc::*;
+
+#[test]
+fn request_round_trips_through_bytes() {
+    let request = JrpcRequest::new(
+        "some/call",
+        Some(serde_json::json!({"a": [1, 2]})),
+        Some("x".into()),
+    )
+    .unwrap();
+
+    let bytes = request.to_bytes().unwrap();
+    let parsed = JrpcRequest::from_bytes(&bytes).unwrap();
+
+    assert_eq!(parsed.get_method(), "some/call");
+    assert_eq!(parsed.get_params(), request.get_params());
+    assert_eq!(parsed.get_id(), request.get_id());
+}
+
+#[test]
+fn response_round_trips_through_bytes() {
+    let error = JrpcError::new(-32602, "Invalid params", Some("a".into()));
+    let response = JrpcResponse::new(None, Some(error), 3.into()).unwrap();
+
+    let bytes = response.to_bytes().unwrap();
+    let parsed = JrpcResponse::from_bytes(&bytes).unwrap();
+
+    assert_eq!(parsed.get_id(), &JsonValue::from(3));
+    assert_eq!(parsed.get_error().as_ref().unwrap().get_code(), &-32602);
+    assert!(parsed.get_result().is_none());
+}
+
+#[test]
+fn invalid_bytes_are_rejected() {
+    match JrpcRequest::from_bytes(br#"{"jsonrpc": "1.0", "method": "a", "id": 1}"#) {
+        Err(ErrorVariant::InvalidJsonRpcVersion) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match JrpcResponse::from_bytes(b"\xff") {
+        Err(ErrorVariant::JsonParseError(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    // Valid JSON, but not a message
+    match JrpcRequest::from_bytes(br#"{"jsonrpc": "2.0", "id": 1}"#) {
+        Err(ErrorVariant::InvalidRequest(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match JrpcResponse::from_bytes(br#"{"jsonrpc": "2.0", "result": 2}"#) {
+        Err(ErrorVariant::InvalidRequest(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
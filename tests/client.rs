@@ -0,0 +1,134 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn generated_ids_are_monotonic() {
+    let generator = IdGenerator::new();
+
+    let first = JrpcRequest::new_with_generated_id(&generator, "a", None).unwrap();
+    let second = JrpcRequest::new_with_generated_id(&generator, "a", None).unwrap();
+    assert_eq!(first.get_id(), &Some(JsonValue::from(1)));
+    assert_eq!(second.get_id(), &Some(JsonValue::from(2)));
+
+    let generator = IdGenerator::starting_at(100);
+    assert_eq!(generator.next_id(), JsonValue::from(100));
+}
+
+#[test]
+fn generated_ids_are_unique_across_threads() {
+    let generator = Arc::new(IdGenerator::new());
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let generator = generator.clone();
+            thread::spawn(move || {
+                let ids: Vec<u64> = (0..1000)
+                    .map(|_| generator.next_id().as_u64().unwrap())
+                    .collect();
+
+                // Each thread observes its own ids in increasing order
+                assert!(ids.windows(2).all(|w| w[0] < w[1]));
+                ids
+            })
+        })
+        .collect();
+
+    let ids: HashSet<u64> = threads
+        .into_iter()
+        .flat_map(|t| t.join().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 8000);
+    assert_eq!(ids.iter().max(), Some(&8000));
+}
+
+/// Reply to every request with `reply`, with the id of the request replaced by `id` if any
+fn replying(reply: &'static str, id: Option<i64>) -> JrpcClient<'static> {
+    JrpcClient::from_blocking(move |message: String| {
+        let request = JrpcRequest::parse(message)?;
+        let id = id.map(JsonValue::from).or_else(|| request.get_id().clone());
+        let reply = reply.replace("$id", &id.unwrap().to_string());
+        Ok(reply)
+    })
+}
+
+#[test]
+fn responses_are_correlated_by_id() {
+    let client = replying(r#"{"jsonrpc": "2.0", "result": 7, "id": $id}"#, None);
+    let response = client.call("a", None).unwrap().wait().unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(7)));
+
+    let client = replying(r#"{"jsonrpc": "2.0", "result": 7, "id": $id}"#, Some(-1));
+    let request = JrpcRequest::new("a", None, Some(JsonValue::from(3))).unwrap();
+    match client.send_request(request).unwrap().wait() {
+        Err(ErrorVariant::IdMismatch(expected, found)) => {
+            assert_eq!(expected, JsonValue::from(3));
+            assert_eq!(found, JsonValue::from(-1));
+        }
+        r => panic!("Unexpected outcome {:?}", r),
+    }
+
+    let client = replying(
+        r#"{"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}"#,
+        None,
+    );
+    let response = client.call("a", None).unwrap().wait().unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32700);
+}
+
+#[test]
+fn calls_are_numbered_across_clones() {
+    let client = replying(r#"{"jsonrpc": "2.0", "result": $id, "id": $id}"#, None);
+    let call = |client: &JrpcClient| client.call("a", None).unwrap().wait().unwrap();
+
+    assert_eq!(call(&client).get_id(), &JsonValue::from(1));
+    assert_eq!(call(&client.clone()).get_id(), &JsonValue::from(2));
+    assert_eq!(call(&client).get_result(), &Some(JsonValue::from(3)));
+}
+
+#[test]
+fn null_results_are_returned_to_the_caller() {
+    let client = replying(r#"{"jsonrpc": "2.0", "result": null, "id": $id}"#, None);
+    let response = client.call("a", None).unwrap().wait().unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::Null));
+    assert!(response.get_error().is_none());
+}
+
+#[test]
+fn pending_calls_are_resolved_out_of_order() {
+    let pending = PendingCalls::new();
+    let response = |id: i64| {
+        JrpcResponse::new(Some(JsonValue::from(id * 10)), None, JsonValue::from(id)).unwrap()
+    };
+
+    let first = pending.register_call(JsonValue::from(1)).unwrap();
+    let second = pending.register_call(JsonValue::from(2)).unwrap();
+    let third = pending.register_call(JsonValue::from(3)).unwrap();
+    assert!(matches!(
+        pending.register_call(JsonValue::from(2)),
+        Err(ErrorVariant::DuplicateRequestId(_))
+    ));
+    assert_eq!(pending.len().unwrap(), 3);
+
+    pending.resolve(response(2)).unwrap();
+    pending.resolve(response(1)).unwrap();
+    assert_eq!(
+        second.wait().unwrap().get_result(),
+        &Some(JsonValue::from(20))
+    );
+    assert_eq!(
+        first.wait().unwrap().get_result(),
+        &Some(JsonValue::from(10))
+    );
+
+    match pending.resolve(response(2)) {
+        Err(ErrorVariant::UnmatchedResponse(id)) => assert_eq!(id, JsonValue::from(2)),
+        r => panic!("Unexpected outcome {:?}", r),
+    }
+
+    assert!(pending.cancel(&JsonValue::from(3)).unwrap());
+    assert!(matches!(third.wait(), Err(ErrorVariant::NoResponse(_))));
+    assert!(pending.is_empty().unwrap());
+}
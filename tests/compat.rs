@@ -0,0 +1,96 @@
+use futures03::executor::block_on;
+use futures_jsonrpc::compat::{method_future, params_as};
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+
+/// Reply with the params, from `async` code
+struct AsyncEcho;
+
+impl<'a> JrpcMethodTrait<'a> for AsyncEcho {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+        Ok(method_future(
+            request,
+            async move { Ok::<_, JrpcError>(params) },
+        ))
+    }
+}
+
+#[test]
+fn messages_can_be_awaited() {
+    let handler = JrpcHandler::new().unwrap();
+    handler.register_method("some/echo", AsyncEcho).unwrap();
+
+    block_on(async {
+        let response = handler
+            .handle_message_async(
+                r#"{"jsonrpc": "2.0", "method": "some/echo", "params": [1], "id": 1}"#,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.get_result(), &Some(serde_json::json!([1])));
+
+        let notification = handler
+            .handle_message_async(r#"{"jsonrpc": "2.0", "method": "some/echo"}"#)
+            .await
+            .unwrap();
+        assert!(notification.is_none());
+
+        match handler
+            .handle_message_async(r#"{"jsonrpc": "2.0", "method": "unknown", "id": 1}"#)
+            .await
+        {
+            Err(ErrorVariant::MethodSignatureNotFound(_)) => (),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    });
+}
+
+generate_async_method!(
+    AsyncCopyParams,
+    async fn handle(&self, request: JrpcRequest) -> Result<Option<JrpcResponse>, ErrorVariant> {
+        let result = JrpcResponseParam::generate_result(request.params_or_null())?;
+        request.generate_response(result).map(Some)
+    }
+);
+
+generate_async_method!(
+    AsyncAdd,
+    i64,
+    async fn handle(&self, request: JrpcRequest) -> Result<Option<JrpcResponse>, ErrorVariant> {
+        let sum = params_as::<i64>(&request)? + self.get_data();
+        let result = JrpcResponseParam::generate_result(JsonValue::from(sum))?;
+        request.generate_response(result).map(Some)
+    }
+);
+
+#[test]
+fn async_methods_are_generated_by_the_macro() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", AsyncCopyParams::new(()).unwrap())
+        .unwrap()
+        .register_method("some/add", AsyncAdd::new(10).unwrap())
+        .unwrap();
+
+    let response = handler
+        .handle_message_blocking(
+            r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": [1], "id": 1}"#,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(serde_json::json!([1])));
+
+    let response = handler
+        .handle_message_blocking(
+            r#"{"jsonrpc": "2.0", "method": "some/add", "params": 5, "id": 2}"#,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(15)));
+}
@@ -0,0 +1,147 @@
+use futures_jsonrpc::compression::{
+    handle_compressed_message, handle_compressed_message_with_limit, ContentEncoding,
+    COMPRESSION_THRESHOLD,
+};
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+fn handler() -> JrpcHandler<'static> {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("copy_params", CopyParams::new().unwrap())
+        .unwrap();
+    handler
+}
+
+#[test]
+fn compressed_request_and_response_round_trip() {
+    let handler = handler();
+    let payload = "x".repeat(COMPRESSION_THRESHOLD * 2);
+    let message = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "copy_params",
+        "params": [payload],
+        "id": 1
+    })
+    .to_string();
+
+    for encoding in [ContentEncoding::Gzip, ContentEncoding::Deflate].iter() {
+        let body = encoding.encode(&message).unwrap();
+        assert!(body.len() < message.len());
+
+        let (reply_encoding, reply) = handle_compressed_message(
+            &handler,
+            &body,
+            Some(encoding.as_header()),
+            Some(encoding.as_header()),
+        )
+        .unwrap()
+        .wait()
+        .unwrap()
+        .unwrap();
+        assert_eq!(reply_encoding, *encoding);
+
+        let response = JrpcResponse::parse(reply_encoding.decode(&reply).unwrap()).unwrap();
+        assert_eq!(response.get_id(), &JsonValue::from(1));
+        assert_eq!(
+            response.get_result(),
+            &Some(serde_json::json!([payload.clone()]))
+        );
+    }
+}
+
+#[test]
+fn small_replies_are_not_compressed() {
+    let handler = handler();
+    let message = r#"{"jsonrpc": "2.0", "method": "copy_params", "params": [1], "id": 1}"#;
+    let body = ContentEncoding::Gzip.encode(message).unwrap();
+
+    let (encoding, reply) =
+        handle_compressed_message(&handler, &body, Some("gzip"), Some("gzip, deflate"))
+            .unwrap()
+            .wait()
+            .unwrap()
+            .unwrap();
+    assert_eq!(encoding, ContentEncoding::Identity);
+    assert!(JrpcResponse::parse(String::from_utf8(reply).unwrap()).is_ok());
+}
+
+#[test]
+fn encodings_are_negotiated() {
+    assert_eq!(
+        ContentEncoding::negotiate("deflate, gzip;q=0.5"),
+        ContentEncoding::Gzip
+    );
+    assert_eq!(
+        ContentEncoding::negotiate("gzip;q=0, deflate"),
+        ContentEncoding::Deflate
+    );
+    assert_eq!(ContentEncoding::negotiate("br"), ContentEncoding::Identity);
+
+    match handle_compressed_message(&handler(), b"{}", Some("br"), None) {
+        Err(ErrorVariant::UnsupportedEncoding(e)) => assert_eq!(e, "br"),
+        Err(e) => panic!("Unexpected error {}", e),
+        Ok(_) => panic!("Unsupported encoding was accepted"),
+    }
+}
+
+#[test]
+fn oversize_bodies_are_refused() {
+    // Compresses to about a kilobyte
+    let bomb = ContentEncoding::Gzip.encode(&" ".repeat(1 << 20)).unwrap();
+    assert!(bomb.len() < 4096);
+
+    match ContentEncoding::Gzip.decode_limited(&bomb, 1024) {
+        Err(ErrorVariant::MessageTooLarge(limit)) => assert_eq!(limit, 1024),
+        other => panic!("Unexpected outcome {:?}", other.map(|m| m.len())),
+    }
+    assert_eq!(
+        ContentEncoding::Gzip
+            .decode_limited(&bomb, 1 << 20)
+            .unwrap()
+            .len(),
+        1 << 20
+    );
+
+    match handle_compressed_message_with_limit(&handler(), &bomb, Some("gzip"), None, 1024) {
+        Err(ErrorVariant::MessageTooLarge(_)) => (),
+        Err(e) => panic!("Unexpected error {}", e),
+        Ok(_) => panic!("Oversize body was accepted"),
+    }
+}
+
+#[test]
+fn undecodable_bodies_are_answered_with_parse_errors() {
+    let handler = handler();
+    let parse_error = |body: &[u8], encoding: &str| {
+        let (_, reply) = handle_compressed_message(&handler, body, Some(encoding), None)
+            .unwrap()
+            .wait()
+            .unwrap()
+            .unwrap();
+        let response = JrpcResponse::parse(String::from_utf8(reply).unwrap()).unwrap();
+        assert_eq!(response.get_id(), &JsonValue::Null);
+        *response.get_error().as_ref().unwrap().get_code()
+    };
+
+    assert_eq!(parse_error(b"not gzip", "gzip"), -32700);
+    assert_eq!(parse_error(&[0xff, 0xfe], "identity"), -32700);
+}
@@ -0,0 +1,231 @@
+use futures_jsonrpc::futures::future;
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+const WRITERS: usize = 4;
+const READERS: usize = 8;
+const RUN_FOR: Duration = Duration::from_millis(500);
+
+fn call(handler: &JrpcHandler, method: &str, value: usize) -> Result<(), ErrorVariant> {
+    let message = format!(
+        r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}, "id": {}}}"#,
+        method, value, value
+    );
+
+    let response = handler
+        .handle_message(message)
+        .and_then(|future| future.wait())?
+        .expect("A request always has a response");
+
+    assert_eq!(response.get_result(), &Some(JsonValue::from(value)));
+    assert_eq!(response.get_id(), &JsonValue::from(value));
+    Ok(())
+}
+
+#[test]
+fn concurrent_registration_and_dispatch() {
+    let handler: JrpcHandler<'static> = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/stable", CopyParams::new().unwrap())
+        .unwrap();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (done, finished) = mpsc::channel();
+
+    for writer in 0..WRITERS {
+        let handler = handler.clone();
+        let stop = stop.clone();
+        let done = done.clone();
+
+        thread::spawn(move || {
+            let mut cycles = 0;
+            while !stop.load(Ordering::SeqCst) {
+                handler
+                    .register_method("some/volatile", CopyParams::new().unwrap())
+                    .unwrap();
+                handler
+                    .register_method(
+                        format!("some/private{}", writer),
+                        CopyParams::new().unwrap(),
+                    )
+                    .unwrap();
+                assert!(handler
+                    .unregister_method(format!("some/private{}", writer))
+                    .unwrap());
+                handler.unregister_method("some/volatile").unwrap();
+                cycles += 1;
+            }
+            done.send(("writer", cycles)).unwrap();
+        });
+    }
+
+    for reader in 0..READERS {
+        let handler = handler.clone();
+        let stop = stop.clone();
+        let done = done.clone();
+
+        thread::spawn(move || {
+            let mut cycles = 0;
+            while !stop.load(Ordering::SeqCst) {
+                call(&handler, "some/stable", reader * 1000 + cycles % 1000).unwrap();
+
+                match call(&handler, "some/volatile", cycles) {
+                    Ok(()) | Err(ErrorVariant::MethodSignatureNotFound(_)) => (),
+                    Err(e) => panic!("Unexpected dispatch failure {:?}", e),
+                }
+                cycles += 1;
+            }
+            done.send(("reader", cycles)).unwrap();
+        });
+    }
+    drop(done);
+
+    let started = Instant::now();
+    thread::sleep(RUN_FOR);
+    stop.store(true, Ordering::SeqCst);
+
+    // A thread that panicked drops its sender without reporting; a deadlocked one never reports
+    for _ in 0..WRITERS + READERS {
+        match finished.recv_timeout(Duration::from_secs(10)) {
+            Ok((role, cycles)) => assert!(cycles > 0, "A {} made no progress", role),
+            Err(e) => panic!(
+                "Worker failed or deadlocked after {:?}: {}",
+                started.elapsed(),
+                e
+            ),
+        }
+    }
+
+    assert!(handler.unregister_method("some/stable").unwrap());
+    assert!(!handler.unregister_method("some/volatile").unwrap());
+    assert_eq!(handler.inflight_count().unwrap(), 0);
+}
+
+#[test]
+fn exactly_one_racing_insert_wins() {
+    for _ in 0..200 {
+        let handler: JrpcHandler<'static> = JrpcHandler::new().unwrap();
+        let barrier = Arc::new(Barrier::new(2));
+
+        let racers: Vec<_> = (0..2)
+            .map(|_| {
+                let handler = handler.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    handler
+                        .register_method_if_absent("some/plugin", CopyParams::new().unwrap())
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        let inserted: Vec<bool> = racers.into_iter().map(|r| r.join().unwrap()).collect();
+        assert_eq!(inserted.iter().filter(|i| **i).count(), 1);
+        call(&handler, "some/plugin", 1).unwrap();
+    }
+}
+
+#[test]
+fn clones_share_their_methods_among_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<JrpcHandler<'static>>();
+
+    let handler: JrpcHandler<'static> = JrpcHandler::new().unwrap();
+
+    let callers: Vec<_> = (0..READERS)
+        .map(|reader| {
+            let handler = handler.clone();
+            thread::spawn(move || {
+                handler
+                    .register_method(format!("some/copy{}", reader), CopyParams::new().unwrap())
+                    .unwrap();
+                (0..100).for_each(|i| call(&handler, "some/copy0", i).unwrap_or(()));
+            })
+        })
+        .collect();
+    callers.into_iter().for_each(|c| c.join().unwrap());
+
+    // Every clone registered into the registry of the original handler
+    for reader in 0..READERS {
+        call(&handler, &format!("some/copy{}", reader), reader).unwrap();
+    }
+}
+
+/// Counts its calls through an `Rc`, so it can't be shared among threads
+struct LocalCounter(Rc<Cell<u64>>);
+
+impl<'a> JrpcMethodTrait<'a> for LocalCounter {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        self.0.set(self.0.get() + 1);
+        let response = JrpcResponseParam::generate_result(JsonValue::from(self.0.get()))
+            .and_then(|result| request.generate_response(result))?;
+        Ok(Box::new(future::ok(Some(response))))
+    }
+}
+
+impl<'a> JrpcOutcomeMethodTrait<'a> for LocalCounter {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = MethodOutcome, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let future = JrpcMethodTrait::generate_future(self, request)?;
+        Ok(Box::new(future.map(MethodOutcome::from)))
+    }
+}
+
+#[test]
+fn methods_are_not_required_to_be_thread_safe() {
+    let calls = Rc::new(Cell::new(0));
+    let method = LocalCounter(calls.clone());
+
+    let request = JrpcRequest::parse_str(r#"{"jsonrpc": "2.0", "method": "count", "id": 1}"#);
+    let response = request
+        .and_then(|request| JrpcMethodTrait::generate_future(&method, request))
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+    assert_eq!(calls.get(), 1);
+
+    // Neither are the methods of the other traits
+    let request = JrpcRequest::parse_str(r#"{"jsonrpc": "2.0", "method": "count", "id": 2}"#);
+    let outcome = request
+        .and_then(|request| JrpcOutcomeMethodTrait::generate_future(&method, request))
+        .and_then(|future| future.wait())
+        .unwrap();
+
+    assert!(outcome.is_response());
+    assert_eq!(calls.get(), 2);
+}
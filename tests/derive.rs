@@ -0,0 +1,83 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct SubtractParams {
+    minuend: i64,
+    subtrahend: i64,
+}
+
+#[jrpc_method]
+async fn subtract(params: SubtractParams) -> Result<i64, JrpcError> {
+    Ok(params.minuend - params.subtrahend)
+}
+
+#[jrpc_method]
+async fn always_fails() -> Result<(), JrpcError> {
+    Err(JrpcError::new(-32000, "Always fails", None))
+}
+
+fn call(handler: &JrpcHandler, message: &str) -> Option<JrpcResponse> {
+    handler
+        .handle_message(message)
+        .and_then(|future| future.wait())
+        .unwrap()
+}
+
+fn handler() -> JrpcHandler<'static> {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("math/subtract", subtract_method())
+        .and_then(|h| h.register_method("some/alwaysFails", always_fails_method()))
+        .unwrap();
+    handler
+}
+
+#[test]
+fn async_method_replies_with_the_result() {
+    let response = call(
+        &handler(),
+        r#"{"jsonrpc": "2.0", "method": "math/subtract", "params": {"minuend": 42, "subtrahend": 23}, "id": 1}"#,
+    )
+    .unwrap();
+
+    assert_eq!(response.get_result(), &Some(JsonValue::from(19)));
+    assert_eq!(response.get_id(), &JsonValue::from(1));
+}
+
+#[test]
+fn async_method_replies_with_the_error() {
+    let response = call(
+        &handler(),
+        r#"{"jsonrpc": "2.0", "method": "some/alwaysFails", "id": "a"}"#,
+    )
+    .unwrap();
+
+    assert!(response.get_result().is_none());
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32000);
+}
+
+#[test]
+fn async_method_rejects_invalid_params() {
+    let result = handler().handle_message(
+        r#"{"jsonrpc": "2.0", "method": "math/subtract", "params": {"minuend": 42}, "id": 1}"#,
+    );
+
+    match result.map(|_| ()) {
+        Err(e) => match e.get_cause() {
+            ErrorVariant::InvalidParams(_) => (),
+            other => panic!("Unexpected error {:?}", other),
+        },
+        Ok(_) => panic!("Invalid params were accepted"),
+    }
+}
+
+#[test]
+fn async_method_ignores_notifications() {
+    let response = call(
+        &handler(),
+        r#"{"jsonrpc": "2.0", "method": "math/subtract", "params": {"minuend": 1, "subtrahend": 1}}"#,
+    );
+    assert!(response.is_none());
+}
@@ -0,0 +1,1564 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+generate_method!(
+    AlwaysFails,
+    impl Future for AlwaysFails {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            Err(ErrorVariant::InternalErrorMessage("bad params".to_string()))
+        }
+    }
+);
+
+generate_method!(
+    WrongId,
+    impl Future for WrongId {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let response = JrpcResponse::new(Some(JsonValue::Null), None, JsonValue::from("1"))?;
+            Ok(Async::Ready(Some(response)))
+        }
+    }
+);
+
+generate_method!(
+    EchoSignature,
+    impl Future for EchoSignature {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let signature = request
+                .get_matched_signature()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(signature)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+generate_method!(
+    RejectsParams,
+    impl Future for RejectsParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let code = request.param::<i32>("code")?;
+
+            let message = JrpcResponseParam::generate_error(JrpcError::new(code, "Rejected", None))
+                .and_then(|error| request.generate_response(error))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+fn call(handler: &JrpcHandler, method: &str, params: JsonValue) -> Option<JrpcResponse> {
+    let message = JrpcRequest::new(method, Some(params), Some(JsonValue::from(1)))
+        .and_then(|request| serde_json::to_string(&request).map_err(ErrorVariant::JsonParseError))
+        .unwrap();
+
+    handler
+        .handle_message(message)
+        .and_then(|future| future.wait())
+        .unwrap()
+}
+
+#[test]
+fn reserved_signatures_are_rejected() {
+    let handler = JrpcHandler::new().unwrap();
+
+    match handler.register_method("rpc.discover", CopyParams::new().unwrap()) {
+        Err(ErrorVariant::ReservedMethodSignature(s)) => assert_eq!(s, "rpc.discover"),
+        _ => panic!("The reserved signature was registered"),
+    }
+
+    match handler.handle_message(r#"{"jsonrpc": "2.0", "method": "rpc.discover", "id": 1}"#) {
+        Err(ErrorVariant::MethodSignatureNotFound(_)) => (),
+        _ => panic!("The reserved signature is reachable"),
+    }
+}
+
+#[test]
+fn system_methods_can_use_reserved_signatures() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_system_method("rpc.discover", CopyParams::new().unwrap())
+        .unwrap();
+
+    let response = call(&handler, "rpc.discover", JsonValue::from(3)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(3)));
+}
+
+#[test]
+fn handler_can_be_collected_from_methods() {
+    let methods: Vec<(&str, Box<dyn JrpcMethodTrait + Send + Sync>)> = vec![
+        ("some/copyParams", Box::new(CopyParams::new().unwrap())),
+        ("other/copyParams", Box::new(CopyParams::new().unwrap())),
+    ];
+    let handler: JrpcHandler = methods.into_iter().collect();
+
+    let response = call(&handler, "other/copyParams", JsonValue::from("x")).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from("x")));
+}
+
+#[test]
+fn handler_from_iter_rejects_reserved_signatures() {
+    let methods: Vec<(&str, Box<dyn JrpcMethodTrait + Send + Sync>)> = vec![
+        ("some/copyParams", Box::new(CopyParams::new().unwrap())),
+        ("rpc.copyParams", Box::new(CopyParams::new().unwrap())),
+    ];
+
+    match JrpcHandler::try_from_iter(methods) {
+        Err(ErrorVariant::ReservedMethodSignature(_)) => (),
+        _ => panic!("The reserved signature was registered"),
+    }
+}
+
+#[test]
+fn method_errors_keep_their_variant_and_reach_the_hook() {
+    let failures = Arc::new(Mutex::new(vec![]));
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/fails", AlwaysFails::new().unwrap())
+        .and_then(|h| h.register_method("some/reject", RejectsParams::new().unwrap()))
+        .unwrap();
+
+    let hook_failures = failures.clone();
+    handler
+        .on_internal_error(move |method, error| {
+            hook_failures
+                .lock()
+                .unwrap()
+                .push(format!("{}: {}", method, error));
+        })
+        .unwrap();
+
+    let error = handler
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/fails", "id": 1}"#)
+        .and_then(|future| future.wait())
+        .unwrap_err();
+
+    match &error {
+        ErrorVariant::InternalErrorMessage(s) => assert_eq!(s, "bad params"),
+        e => panic!("Unexpected error {:?}", e),
+    }
+    assert!(error.get_method().is_none());
+    assert_eq!(JrpcError::from(error).get_code(), &-32603);
+
+    // Failures caused by the client don't reach the hook
+    match handler
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/reject", "params": {}, "id": 1}"#)
+        .and_then(|future| future.wait())
+    {
+        Err(ErrorVariant::InvalidParams(_)) => (),
+        r => panic!("Unexpected outcome {:?}", r),
+    }
+
+    assert_eq!(
+        failures.lock().unwrap().as_slice(),
+        &["some/fails: An error ocurred: bad params".to_string()]
+    );
+}
+
+fn batch(handler: &JrpcHandler, message: &str) -> Option<JrpcBatchOutcome> {
+    handler
+        .handle_batch_message(message)
+        .and_then(|future| future.wait())
+        .unwrap()
+}
+
+#[test]
+fn broken_batch_is_answered_with_a_single_parse_error() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let message = r#"[
+        {"jsonrpc": "2.0", "method": "some/copyParams", "params": [1], "id": 1},
+        {"jsonrpc": "2.0", "method"
+    ]"#;
+
+    match batch(&handler, message) {
+        Some(JrpcBatchOutcome::Single(response)) => {
+            assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32700);
+            assert_eq!(response.get_id(), &JsonValue::Null);
+        }
+        other => panic!("Unexpected outcome {:?}", other),
+    }
+
+    match batch(&handler, "[]") {
+        Some(JrpcBatchOutcome::Single(response)) => {
+            assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32600);
+        }
+        other => panic!("Unexpected outcome {:?}", other),
+    }
+}
+
+#[test]
+fn batch_with_bad_elements_is_answered_per_element() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let message = r#"[
+        {"jsonrpc": "2.0", "method": "some/copyParams", "params": [1], "id": 1},
+        {"jsonrpc": "2.0", "method": "some/copyParams", "params": [2]},
+        1,
+        {"jsonrpc": "2.0", "method": "other/missing", "id": "b"}
+    ]"#;
+
+    let responses = match batch(&handler, message) {
+        Some(JrpcBatchOutcome::Batch(responses)) => responses.into_responses(),
+        other => panic!("Unexpected outcome {:?}", other),
+    };
+
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0].get_id(), &JsonValue::from(1));
+    assert_eq!(responses[0].get_result(), &Some(serde_json::json!([1])));
+    assert_eq!(responses[1].get_id(), &JsonValue::Null);
+    assert_eq!(
+        responses[1].get_error().as_ref().unwrap().get_code(),
+        &-32600
+    );
+    assert_eq!(responses[2].get_id(), &JsonValue::from("b"));
+    assert_eq!(
+        responses[2].get_error().as_ref().unwrap().get_code(),
+        &-32601
+    );
+}
+
+#[test]
+fn batch_of_notifications_has_no_reply() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let message = r#"[
+        {"jsonrpc": "2.0", "method": "some/copyParams", "params": [1]},
+        {"jsonrpc": "2.0", "method": "other/missing"}
+    ]"#;
+
+    assert!(batch(&handler, message).is_none());
+}
+
+#[test]
+fn filtered_methods_are_disabled() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .and_then(|h| h.register_method("admin/copyParams", CopyParams::new().unwrap()))
+        .and_then(|h| h.set_method_filter(|method| !method.starts_with("admin/")))
+        .unwrap();
+
+    let response = call(&handler, "some/copyParams", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+
+    match handler.handle_message(r#"{"jsonrpc": "2.0", "method": "admin/copyParams", "id": 1}"#) {
+        Err(e @ ErrorVariant::MethodDisabled(_)) => {
+            assert_eq!(JrpcError::from(e).get_code(), &-32601)
+        }
+        _ => panic!("The filtered method was dispatched"),
+    }
+
+    handler.clear_method_filter().unwrap();
+    let response = call(&handler, "admin/copyParams", JsonValue::from(2)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(2)));
+}
+
+#[test]
+fn mismatched_response_ids_fail_in_strict_mode() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/wrongId", WrongId::new().unwrap())
+        .unwrap();
+
+    let response = call(&handler, "some/wrongId", JsonValue::Null).unwrap();
+    assert_eq!(response.get_id(), &JsonValue::from("1"));
+
+    handler.set_strict_mode(true).unwrap();
+    let error = handler
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/wrongId", "id": 1}"#)
+        .and_then(|future| future.wait())
+        .unwrap_err();
+
+    match error {
+        ErrorVariant::IdMismatch(expected, found) => {
+            assert_eq!(expected, JsonValue::from(1));
+            assert_eq!(found, JsonValue::from("1"));
+        }
+        e => panic!("Unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn borrowed_and_owned_messages_are_handled() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let message = r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 5, "id": 1}"#;
+    let owned = String::from(message);
+    let borrowed = handler
+        .handle_str(message)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    let owned = handler
+        .handle_message(owned)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(borrowed.get_result(), &Some(JsonValue::from(5)));
+    assert_eq!(borrowed.get_result(), owned.get_result());
+
+    // Any displayable message is accepted, such as a JSON value
+    let value = serde_json::from_str::<JsonValue>(message).unwrap();
+    let displayed = handler
+        .handle_message(value)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert_eq!(displayed.get_result(), owned.get_result());
+}
+
+#[test]
+fn raw_preprocessor_transforms_messages_before_parsing() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("SOME/COPYPARAMS", CopyParams::new().unwrap())
+        .and_then(|h| {
+            h.set_raw_preprocessor(|message| {
+                if message.starts_with('{') {
+                    Ok(message.replace("some/copyParams", "SOME/COPYPARAMS"))
+                } else {
+                    Err(JrpcError::new(-32001, "Unsigned message", None))
+                }
+            })
+        })
+        .unwrap();
+
+    let response = call(&handler, "some/copyParams", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+
+    let response = handler
+        .handle_message(r#"["signed?"]"#)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32001);
+    assert_eq!(response.get_id(), &JsonValue::Null);
+}
+
+#[test]
+fn raw_ids_are_echoed_exactly() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let respond = |id: &str| {
+        let message = format!(
+            r#"{{"jsonrpc": "2.0", "method": "some/copyParams", "params": 1, "id": {}}}"#,
+            id
+        );
+        let response = handler
+            .handle_message(message)
+            .and_then(|future| future.wait())
+            .unwrap()
+            .unwrap();
+        serde_json::to_string(&response).unwrap()
+    };
+
+    assert_eq!(respond("1e2"), r#"{"jsonrpc":"2.0","result":1,"id":100.0}"#);
+
+    handler.set_preserve_raw_ids(true).unwrap();
+    assert_eq!(
+        respond("100.0"),
+        r#"{"jsonrpc":"2.0","result":1,"id":100.0}"#
+    );
+    assert_eq!(respond("1e2"), r#"{"jsonrpc":"2.0","result":1,"id":1e2}"#);
+    assert_eq!(
+        respond(r#""a""#),
+        r#"{"jsonrpc":"2.0","result":1,"id":"a"}"#
+    );
+}
+
+#[test]
+fn raw_ids_are_echoed_exactly_in_batches() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .and_then(|h| h.set_preserve_raw_ids(true))
+        .and_then(|h| h.set_lenient_version(true))
+        .unwrap();
+
+    let message = r#"[
+        {"jsonrpc": "2.0", "method": "some/copyParams", "params": 1, "id": 1e2},
+        {"method": "some/copyParams", "params": 2, "id": 100.0}
+    ]"#;
+    let responses = match batch(&handler, message) {
+        Some(JrpcBatchOutcome::Batch(responses)) => responses,
+        other => panic!("Unexpected outcome {:?}", other),
+    };
+    assert_eq!(
+        serde_json::to_string(&responses).unwrap(),
+        r#"[{"jsonrpc":"2.0","result":1,"id":1e2},{"jsonrpc":"2.0","result":2,"id":100.0}]"#
+    );
+
+    let message = r#"{"method": "some/copyParams", "params": 3, "id": 1e2}"#;
+    match batch(&handler, message) {
+        Some(JrpcBatchOutcome::Single(response)) => assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"jsonrpc":"2.0","result":3,"id":1e2}"#
+        ),
+        other => panic!("Unexpected outcome {:?}", other),
+    }
+}
+
+#[test]
+fn large_ids_are_echoed_exactly() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let respond = |id: &str| {
+        let message = format!(
+            r#"{{"jsonrpc": "2.0", "method": "some/copyParams", "params": 1, "id": {}}}"#,
+            id
+        );
+        let response = handler
+            .handle_message(message)
+            .and_then(|future| future.wait())
+            .unwrap()
+            .unwrap();
+        serde_json::to_string(&response).unwrap()
+    };
+
+    // Fits in a u64, but not in the mantissa of a f64
+    assert_eq!(
+        respond("10000000000000001"),
+        r#"{"jsonrpc":"2.0","result":1,"id":10000000000000001}"#
+    );
+
+    handler.set_preserve_raw_ids(true).unwrap();
+    assert_eq!(
+        respond("10000000000000001"),
+        r#"{"jsonrpc":"2.0","result":1,"id":10000000000000001}"#
+    );
+    assert_eq!(
+        respond("100000000000000000001"),
+        r#"{"jsonrpc":"2.0","result":1,"id":100000000000000000001}"#
+    );
+}
+
+#[test]
+fn methods_receive_the_matched_signature() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/echoSignature", EchoSignature::new().unwrap())
+        .unwrap();
+
+    let response = call(&handler, "some/echoSignature", JsonValue::Null).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::Null));
+
+    handler.set_attach_signatures(true).unwrap();
+    let response = call(&handler, "some/echoSignature", JsonValue::Null).unwrap();
+    assert_eq!(
+        response.get_result(),
+        &Some(JsonValue::from("some/echoSignature"))
+    );
+
+    let request = JrpcRequest::new("some/echoSignature", None, None).unwrap();
+    assert!(request.get_matched_signature().is_none());
+
+    // A request reaching the default method matched no registration, despite its method name
+    handler
+        .set_default_method(EchoSignature::new().unwrap())
+        .unwrap();
+    let response = call(&handler, "some/unknown", JsonValue::Null).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::Null));
+}
+
+#[test]
+fn temporary_methods_are_restored() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .set_attach_signatures(true)
+        .unwrap()
+        .register_method("some/method", CopyParams::new().unwrap())
+        .unwrap();
+
+    let result = handler
+        .with_temporary_method("some/method", EchoSignature::new().unwrap(), || {
+            call(&handler, "some/method", JsonValue::from(1)).unwrap()
+        })
+        .unwrap();
+    assert_eq!(result.get_result(), &Some(JsonValue::from("some/method")));
+
+    let result = call(&handler, "some/method", JsonValue::from(1)).unwrap();
+    assert_eq!(result.get_result(), &Some(JsonValue::from(1)));
+
+    handler
+        .with_temporary_method("some/temporary", CopyParams::new().unwrap(), || {
+            assert!(call(&handler, "some/temporary", JsonValue::from(1)).is_some())
+        })
+        .unwrap();
+    assert!(!handler.unregister_method("some/temporary").unwrap());
+}
+
+#[test]
+fn temporary_methods_are_restored_after_a_panic() {
+    let handler = JrpcHandler::new().unwrap();
+
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        handler.with_temporary_method("some/temporary", CopyParams::new().unwrap(), || {
+            panic!("Test body failed")
+        })
+    }));
+
+    assert!(outcome.is_err());
+    assert!(!handler.unregister_method("some/temporary").unwrap());
+}
+
+#[test]
+fn error_codes_are_remapped_into_namespaces() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("user/reject", RejectsParams::new().unwrap())
+        .and_then(|h| h.register_method("user/admin/reject", RejectsParams::new().unwrap()))
+        .and_then(|h| h.register_method("other/reject", RejectsParams::new().unwrap()))
+        .and_then(|h| h.register_method("user/fails", AlwaysFails::new().unwrap()))
+        .and_then(|h| h.set_error_namespace("user/", 1000))
+        .and_then(|h| h.set_error_namespace("user/admin/", 5000))
+        .unwrap();
+
+    let code = |method: &str, requested: i32| {
+        let params = serde_json::json!({ "code": requested });
+        let response = call(&handler, method, params).unwrap();
+        *response.get_error().as_ref().unwrap().get_code()
+    };
+
+    assert_eq!(code("user/reject", -32602), 1602);
+    assert_eq!(code("user/reject", -32000), 1000);
+    assert_eq!(code("user/reject", 42), 42);
+    assert_eq!(code("user/admin/reject", -32602), 5602);
+    assert_eq!(code("other/reject", -32602), -32602);
+
+    let message = r#"[
+        {"jsonrpc": "2.0", "method": "user/fails", "id": 1},
+        {"jsonrpc": "2.0", "method": "user/missing", "id": 2},
+        {"jsonrpc": "2.0", "method": "user/reject", "params": {}, "id": 3}
+    ]"#;
+    let codes: Vec<i32> = match batch(&handler, message) {
+        Some(JrpcBatchOutcome::Batch(responses)) => responses
+            .get_responses()
+            .iter()
+            .map(|r| *r.get_error().as_ref().unwrap().get_code())
+            .collect(),
+        other => panic!("Unexpected outcome {:?}", other),
+    };
+    assert_eq!(codes, vec![1603, -32601, 1602]);
+}
+
+#[test]
+fn internal_errors_carry_backtraces_when_enabled() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/fails", AlwaysFails::new().unwrap())
+        .and_then(|h| h.register_method("some/reject", RejectsParams::new().unwrap()))
+        .unwrap();
+
+    let message = r#"[
+        {"jsonrpc": "2.0", "method": "some/fails", "id": 1},
+        {"jsonrpc": "2.0", "method": "some/reject", "params": {}, "id": 2}
+    ]"#;
+    let errors = |handler: &JrpcHandler| match batch(handler, message) {
+        Some(JrpcBatchOutcome::Batch(responses)) => responses
+            .into_responses()
+            .into_iter()
+            .map(|r| r.get_error().clone().unwrap())
+            .collect::<Vec<JrpcError>>(),
+        other => panic!("Unexpected outcome {:?}", other),
+    };
+
+    let disabled = errors(&handler);
+    assert_eq!(disabled[0].get_code(), &-32603);
+    assert!(disabled[0].get_data().is_none());
+
+    handler.capture_backtraces(true).unwrap();
+    let enabled = errors(&handler);
+    assert_eq!(enabled[0].get_code(), &-32603);
+    assert!(enabled[0].get_data().as_ref().unwrap()["backtrace"].is_string());
+
+    // Only internal errors are affected
+    assert_eq!(enabled[1].get_code(), &-32602);
+    assert!(enabled[1].get_data().is_none());
+
+    let message = r#"{"jsonrpc": "2.0", "method": "some/fails", "id": 1}"#;
+    let response = handler.handle_message_to_response(message).wait().unwrap();
+    let error = response.unwrap().get_error().clone().unwrap();
+    assert!(error.get_data().as_ref().unwrap()["backtrace"].is_string());
+
+    // Callers of handle_message get the error as the method raised it
+    match handler
+        .handle_message(message)
+        .and_then(|future| future.wait())
+    {
+        Err(ErrorVariant::InternalErrorMessage(_)) => (),
+        r => panic!("Unexpected outcome {:?}", r),
+    }
+}
+
+#[test]
+fn dispatch_answers_protocol_errors() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/reject", RejectsParams::new().unwrap())
+        .unwrap();
+
+    let dispatch = |message: &str| {
+        handler
+            .dispatch(message)
+            .and_then(|future| future.wait())
+            .unwrap()
+    };
+
+    let response = dispatch(r#"{"jsonrpc": "2.0", "method": "some/missing", "id": 1}"#).unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32601);
+    assert_eq!(response.get_id(), &JsonValue::from(1));
+
+    let response =
+        dispatch(r#"{"jsonrpc": "2.0", "method": "some/reject", "params": {}, "id": 2}"#);
+    assert_eq!(
+        response.unwrap().get_error().as_ref().unwrap().get_code(),
+        &-32602
+    );
+
+    let response = dispatch(r#"{"jsonrpc": "2.0", "method": "some/missing"}"#);
+    assert!(response.is_none());
+
+    let response = dispatch(r#"{"jsonrpc": "2.0", "#).unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32700);
+    assert_eq!(response.get_id(), &JsonValue::Null);
+}
+
+#[test]
+fn dispatch_returns_internal_faults() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/wrongId", WrongId::new().unwrap())
+        .and_then(|h| h.set_strict_mode(true))
+        .unwrap();
+
+    let result = handler
+        .dispatch(r#"{"jsonrpc": "2.0", "method": "some/wrongId", "id": 1}"#)
+        .and_then(|future| future.wait());
+
+    match result {
+        Err(e @ DispatchError::Internal(_)) => assert!(e.is_internal()),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn dispatch_errors_are_classified_by_cause() {
+    let method_error = |e| ErrorVariant::MethodError("some/method".to_string(), Box::new(e));
+
+    // Failures of a method are told apart, whatever their cause
+    for cause in [
+        ErrorVariant::InvalidParams("a".into()),
+        ErrorVariant::InternalError,
+        ErrorVariant::NoRequestProvided,
+    ] {
+        match DispatchError::from(method_error(cause)) {
+            e @ DispatchError::Method(_, _) => {
+                assert!(!e.is_internal());
+                assert_eq!(e.get_method(), Some(&"some/method".to_string()));
+            }
+            e => panic!("Unexpected classification {:?}", e),
+        }
+    }
+
+    assert!(DispatchError::from(ErrorVariant::RwLockPoisoned).is_internal());
+    assert!(DispatchError::from(ErrorVariant::InternalError).is_internal());
+    assert!(DispatchError::from(ErrorVariant::InternalErrorMessage("a".into())).is_internal());
+    assert!(!DispatchError::from(ErrorVariant::InvalidJsonRpcId).is_internal());
+}
+
+#[test]
+fn batches_return_internal_faults() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/wrongId", WrongId::new().unwrap())
+        .and_then(|h| h.register_method("some/fails", AlwaysFails::new().unwrap()))
+        .unwrap();
+
+    let message = r#"[
+        {"jsonrpc": "2.0", "method": "some/fails", "id": 1},
+        {"jsonrpc": "2.0", "method": "some/wrongId", "id": 2}
+    ]"#;
+
+    // Method failures are answered
+    match batch(&handler, message) {
+        Some(JrpcBatchOutcome::Batch(responses)) => {
+            let responses = responses.into_responses();
+            assert_eq!(
+                responses[0].get_error().as_ref().unwrap().get_code(),
+                &-32603
+            );
+        }
+        other => panic!("Unexpected outcome {:?}", other),
+    }
+
+    // A mismatched response id is a fault of the server, failing the whole batch
+    handler.set_strict_mode(true).unwrap();
+    let result = handler
+        .handle_batch_message(message)
+        .and_then(|future| future.wait());
+    match result {
+        Err(ErrorVariant::IdMismatch(_, _)) => (),
+        other => panic!("Unexpected outcome {:?}", other),
+    }
+}
+
+#[test]
+fn rate_limited_errors_carry_the_retry_delay() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .and_then(|h| h.set_rate_limit("some/copyParams", 2, Duration::from_secs(60)))
+        .unwrap();
+
+    let message = r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 1, "id": 1}"#;
+    let dispatch = || {
+        handler
+            .dispatch(message)
+            .and_then(|future| future.wait())
+            .unwrap()
+            .unwrap()
+    };
+
+    assert!(dispatch().get_error().is_none());
+    assert!(dispatch().get_error().is_none());
+
+    let response = dispatch();
+    let error = response.get_error().as_ref().unwrap();
+    assert_eq!(error.get_code(), &-32000);
+    let retry_after = error.get_data().as_ref().unwrap()["retry_after_ms"]
+        .as_u64()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 60_000);
+
+    match handler.handle_message(message).map(|_| ()) {
+        Err(ErrorVariant::RateLimited(method, _)) => assert_eq!(method, "some/copyParams"),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    assert!(handler.clear_rate_limit("some/copyParams").unwrap());
+    assert!(dispatch().get_error().is_none());
+}
+
+#[test]
+fn snapshots_dispatch_the_captured_methods() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let snapshot = handler.snapshot().unwrap();
+    handler
+        .register_method("some/later", CopyParams::new().unwrap())
+        .unwrap();
+    handler.unregister_method("some/copyParams").unwrap();
+
+    let response = snapshot
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 3, "id": 1}"#)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(3)));
+
+    assert!(!snapshot.contains_method("some/later"));
+    match snapshot.handle_message(r#"{"jsonrpc": "2.0", "method": "some/later", "id": 1}"#) {
+        Err(ErrorVariant::MethodSignatureNotFound(method)) => assert_eq!(method, "some/later"),
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    assert!(handler.snapshot().unwrap().contains_method("some/later"));
+}
+
+#[test]
+fn snapshots_apply_notifications_specs_and_the_default_method() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method_with_schema(
+            "some/copyParams",
+            CopyParams::new().unwrap(),
+            params::ParamsSpec::arity(1),
+        )
+        .unwrap()
+        .set_default_method(CopyParams::new().unwrap())
+        .unwrap();
+
+    let snapshot = handler.snapshot().unwrap();
+    let dispatch = |message: &str| {
+        snapshot
+            .handle_message(message)
+            .and_then(|future| future.wait())
+            .unwrap()
+    };
+
+    assert!(
+        dispatch(r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": [3]}"#).is_none()
+    );
+    assert!(
+        dispatch(r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": [3, 4]}"#).is_none()
+    );
+
+    let response =
+        dispatch(r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": [3, 4], "id": 1}"#)
+            .unwrap();
+    assert_eq!(*response.get_error().as_ref().unwrap().get_code(), -32602);
+
+    let response =
+        dispatch(r#"{"jsonrpc": "2.0", "method": "some/unknown", "params": 5, "id": 2}"#).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(5)));
+}
+
+#[test]
+fn snapshots_parse_messages_as_their_handler() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap()
+        .set_lenient_version(true)
+        .unwrap()
+        .set_preserve_raw_ids(true)
+        .unwrap();
+
+    let snapshot = handler.snapshot().unwrap();
+    let response = snapshot
+        .handle_message(r#"{"method": "some/copyParams", "params": 1, "id": 1.50}"#)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+    assert!(response.to_string().contains(r#""id":1.50"#));
+}
+
+#[test]
+fn dispatched_methods_route_on_the_shape_of_the_params() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .set_attach_signatures(true)
+        .unwrap()
+        .register_dispatched_method("some/overloaded", |request: &JrpcRequest| {
+            let method: Box<dyn JrpcMethodTrait> = match request.get_params() {
+                Some(JsonValue::Object(_)) => Box::new(EchoSignature::new().unwrap()),
+                _ => Box::new(CopyParams::new().unwrap()),
+            };
+            method
+        })
+        .unwrap();
+
+    let response = call(&handler, "some/overloaded", serde_json::json!([1, 2])).unwrap();
+    assert_eq!(response.get_result(), &Some(serde_json::json!([1, 2])));
+
+    let response = call(&handler, "some/overloaded", serde_json::json!({"a": 1})).unwrap();
+    assert_eq!(
+        response.get_result(),
+        &Some(JsonValue::from("some/overloaded"))
+    );
+}
+
+#[test]
+fn error_categories_are_added_when_enabled() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/rejectsParams", RejectsParams::new().unwrap())
+        .unwrap();
+
+    let response = call(
+        &handler,
+        "some/rejectsParams",
+        serde_json::json!({"code": -32602}),
+    )
+    .unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_data(), &None);
+
+    handler.set_error_categories(true).unwrap();
+    let response = call(
+        &handler,
+        "some/rejectsParams",
+        serde_json::json!({"code": -32602}),
+    )
+    .unwrap();
+    assert_eq!(
+        response.get_error().as_ref().unwrap().get_data(),
+        &Some(serde_json::json!({"category": "InvalidParams"}))
+    );
+
+    let response = handler
+        .dispatch(r#"{"jsonrpc": "2.0", "method": "some/missing", "id": 1}"#)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        response.get_error().as_ref().unwrap().get_data(),
+        &Some(serde_json::json!({"category": "MethodNotFound"}))
+    );
+}
+
+#[test]
+fn notification_hook_fires_only_for_notifications() {
+    let handler = JrpcHandler::new().unwrap();
+    let notified = Arc::new(Mutex::new(vec![]));
+    let hook_notified = notified.clone();
+    handler
+        .register_method("copy", CopyParams::new().unwrap())
+        .unwrap()
+        .register_method("fails", AlwaysFails::new().unwrap())
+        .unwrap()
+        .on_notification(move |request| {
+            hook_notified
+                .lock()
+                .unwrap()
+                .push(request.get_method().clone())
+        })
+        .unwrap();
+
+    call(&handler, "copy", JsonValue::from(1));
+    assert!(notified.lock().unwrap().is_empty());
+
+    // The hook fires even though the method fails
+    let message = r#"{"jsonrpc": "2.0", "method": "fails", "params": [1]}"#;
+    assert!(handler.handle_message(message).unwrap().wait().is_err());
+
+    let message = r#"{"jsonrpc": "2.0", "method": "unknown"}"#;
+    assert!(handler.handle_message(message).is_err());
+
+    assert_eq!(*notified.lock().unwrap(), vec!["fails".to_string()]);
+}
+
+generate_method!(
+    EchoMethod,
+    impl Future for EchoMethod {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            assert!(request.get_matched_signature().is_none());
+            let method = JsonValue::from(request.get_method().clone());
+
+            let message = JrpcResponseParam::generate_result(method)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+#[test]
+fn default_method_receives_unknown_methods() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("copy", CopyParams::new().unwrap())
+        .unwrap()
+        .set_default_method(EchoMethod::new().unwrap())
+        .unwrap();
+
+    let response = call(&handler, "some/unknown", JsonValue::Null).unwrap();
+    assert_eq!(
+        response.get_result(),
+        &Some(JsonValue::from("some/unknown"))
+    );
+
+    let response = call(&handler, "copy", JsonValue::from(3)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(3)));
+
+    handler.clear_default_method().unwrap();
+    let message = r#"{"jsonrpc": "2.0", "method": "some/unknown", "id": 1}"#;
+    match handler.handle_message(message) {
+        Err(ErrorVariant::MethodSignatureNotFound(method)) => assert_eq!(method, "some/unknown"),
+        _ => panic!("Expected the method not to be found"),
+    }
+}
+
+#[test]
+fn registered_methods_are_listed() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/b", CopyParams::new().unwrap())
+        .unwrap()
+        .register_method("some/a", CopyParams::new().unwrap())
+        .unwrap();
+    assert_eq!(handler.list_methods().unwrap(), vec!["some/a", "some/b"]);
+
+    assert!(handler.unregister_method("some/b").unwrap());
+    assert!(!handler.unregister_method("some/b").unwrap());
+    assert_eq!(handler.list_methods().unwrap(), vec!["some/a"]);
+}
+
+#[test]
+fn checked_registration_rejects_duplicate_signatures() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .set_attach_signatures(true)
+        .unwrap()
+        .register_method("some/method", CopyParams::new().unwrap())
+        .unwrap();
+
+    // The plain registration overwrites the existing method
+    handler
+        .register_method("some/method", EchoSignature::new().unwrap())
+        .unwrap();
+    let response = call(&handler, "some/method", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from("some/method")));
+
+    match handler.register_method_checked("some/method", CopyParams::new().unwrap()) {
+        Err(ErrorVariant::MethodAlreadyRegistered(s)) => assert_eq!(s, "some/method"),
+        _ => panic!("Expected the duplicate registration to fail"),
+    }
+    let response = call(&handler, "some/method", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from("some/method")));
+
+    handler
+        .register_method_checked("some/other", CopyParams::new().unwrap())
+        .unwrap();
+    let response = call(&handler, "some/other", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+}
+
+#[test]
+fn notifications_resolve_to_none() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/wrongId", WrongId::new().unwrap())
+        .unwrap();
+
+    // The method answers with a response, which is discarded for a notification
+    let message = r#"{"jsonrpc": "2.0", "method": "some/wrongId"}"#;
+    let response = handler
+        .handle_message(message)
+        .and_then(|future| future.wait())
+        .unwrap();
+    assert!(response.is_none());
+
+    let request = JrpcRequest::prepare_to_send_notification("some/wrongId", None).unwrap();
+    let response = handler
+        .handle_request(request)
+        .and_then(|future| future.wait())
+        .unwrap();
+    assert!(response.is_none());
+}
+
+#[test]
+fn closures_can_be_registered_as_methods() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_fn("math/add", |request| {
+            let (a, b): (i64, i64) = request.get_params_as()?;
+            let result = JrpcResponseParam::generate_result(JsonValue::from(a + b))?;
+            request.generate_response(result).map(Some)
+        })
+        .unwrap();
+
+    let response = call(&handler, "math/add", serde_json::json!([2, 3])).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(5)));
+
+    let message = r#"{"jsonrpc": "2.0", "method": "math/add", "params": [2], "id": 1}"#;
+    match handler.handle_message(message).unwrap().wait() {
+        Err(ErrorVariant::InvalidParams(_)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn blocking_dispatch_returns_the_response() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copy", CopyParams::new().unwrap())
+        .unwrap();
+
+    let response = handler
+        .handle_message_blocking(
+            r#"{"jsonrpc": "2.0", "method": "some/copy", "params": 4, "id": 1}"#,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(4)));
+
+    match handler.handle_message_blocking(r#"{"jsonrpc": "2.0", "method": "unknown", "id": 1}"#) {
+        Err(ErrorVariant::MethodSignatureNotFound(method)) => assert_eq!(method, "unknown"),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn failures_are_answered_with_error_responses() {
+    let handler = JrpcHandler::new().unwrap();
+    let respond = |message: &str| handler.handle_message_to_response(message).wait().unwrap();
+
+    let response = respond(r#"{"jsonrpc": "2.0", "method": "unknown", "id": "a"}"#).unwrap();
+    assert_eq!(response.get_id(), &JsonValue::from("a"));
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32601);
+
+    // The id of an invalid request is still read from the message
+    let response = respond(r#"{"jsonrpc": "1.0", "method": "unknown", "id": 5}"#).unwrap();
+    assert_eq!(response.get_id(), &JsonValue::from(5));
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32600);
+
+    let response = respond(r#"{"jsonrpc": "2.0", "method": "unknown", "id": 5"#).unwrap();
+    assert_eq!(response.get_id(), &JsonValue::Null);
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32700);
+
+    assert!(respond(r#"{"jsonrpc": "2.0", "method": "unknown"}"#).is_none());
+}
+
+#[test]
+fn pre_hooks_can_reject_requests() {
+    let handler = JrpcHandler::new().unwrap();
+    let order = Arc::new(Mutex::new(vec![]));
+    let observed = Arc::new(Mutex::new(vec![]));
+
+    let first = order.clone();
+    let second = order.clone();
+    let responses = observed.clone();
+    handler
+        .register_method("admin/copyParams", CopyParams::new().unwrap())
+        .unwrap()
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap()
+        .add_pre_hook(move |request| {
+            first.lock().unwrap().push(1);
+            match request.get_method().starts_with("admin/") {
+                true => Err(JrpcError::new(-32001, "Unauthorized", None)),
+                false => Ok(()),
+            }
+        })
+        .unwrap()
+        .add_pre_hook(move |_| {
+            second.lock().unwrap().push(2);
+            Ok(())
+        })
+        .unwrap()
+        .add_post_hook(move |request, response| {
+            let error = response.get_error().as_ref().map(|e| *e.get_code());
+            responses
+                .lock()
+                .unwrap()
+                .push((request.get_method().clone(), error));
+        })
+        .unwrap();
+
+    let response = call(&handler, "admin/copyParams", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32001);
+    assert_eq!(response.get_id(), &JsonValue::from(1));
+    assert_eq!(*order.lock().unwrap(), vec![1]);
+
+    let response = call(&handler, "some/copyParams", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+    assert_eq!(*order.lock().unwrap(), vec![1, 1, 2]);
+
+    assert_eq!(
+        *observed.lock().unwrap(),
+        vec![
+            ("admin/copyParams".to_string(), Some(-32001)),
+            ("some/copyParams".to_string(), None),
+        ]
+    );
+}
+
+#[test]
+fn pre_hooks_run_in_snapshots() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("admin/copyParams", CopyParams::new().unwrap())
+        .unwrap()
+        .add_pre_hook(|request| match request.get_method().starts_with("admin/") {
+            true => Err(JrpcError::new(-32001, "Unauthorized", None)),
+            false => Ok(()),
+        })
+        .unwrap();
+
+    let response = handler
+        .snapshot()
+        .unwrap()
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "admin/copyParams", "params": 1, "id": 1}"#)
+        .unwrap()
+        .wait()
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32001);
+    assert_eq!(response.get_id(), &JsonValue::from(1));
+}
+
+#[test]
+fn hooks_may_change_the_settings_of_their_handler() {
+    let handler = JrpcHandler::new().unwrap();
+    let hooked = handler.clone();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap()
+        .add_pre_hook(move |_| {
+            hooked.set_error_categories(true).unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+    let response = call(&handler, "some/copyParams", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+
+    let response = handler
+        .handle_message_to_response(r#"{"jsonrpc": "2.0", "method": "some/missing", "id": 2}"#)
+        .wait()
+        .unwrap()
+        .unwrap();
+    assert!(response.get_error().as_ref().unwrap().get_data().is_some());
+}
+
+/// Answer calls with their params, and leave calls without params unanswered
+struct AnswerWithParams;
+
+impl<'a> JrpcOutcomeMethodTrait<'a> for AnswerWithParams {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = MethodOutcome, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let outcome = match request.get_params().clone() {
+            Some(params) => JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))
+                .map(MethodOutcome::Response)?,
+            None => MethodOutcome::NoResponse,
+        };
+
+        Ok(Box::new(futures_jsonrpc::futures::future::ok(outcome)))
+    }
+}
+
+#[test]
+fn methods_can_resolve_to_explicit_outcomes() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_outcome_method("some/answer", AnswerWithParams)
+        .unwrap();
+
+    let response = call(&handler, "some/answer", JsonValue::from(1)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+
+    let response = handler
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/answer", "id": 1}"#)
+        .and_then(|future| future.wait())
+        .unwrap();
+    assert!(response.is_none());
+
+    assert_eq!(MethodOutcome::from(None), MethodOutcome::NoResponse);
+    let response = JrpcResponse::new(Some(JsonValue::Null), None, JsonValue::Null).unwrap();
+    let outcome = MethodOutcome::from(Some(response));
+    assert!(outcome.is_response());
+    assert!(MethodOutcome::NoResponse.into_response().is_none());
+}
+
+#[test]
+fn methods_are_registered_in_bulk() {
+    let handler = JrpcHandler::new().unwrap();
+    assert_eq!(handler.method_count().unwrap(), 0);
+
+    let method = |i: usize| {
+        let method: Box<dyn JrpcMethodTrait + Send + Sync> = Box::new(CopyParams::new().unwrap());
+        (format!("some/copyParams{}", i), method)
+    };
+    handler.register_methods((0..5).map(method)).unwrap();
+    assert_eq!(handler.method_count().unwrap(), 5);
+
+    let response = call(&handler, "some/copyParams4", JsonValue::from(4)).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(4)));
+
+    let reserved: Box<dyn JrpcMethodTrait + Send + Sync> = Box::new(CopyParams::new().unwrap());
+    let methods = vec![method(5), ("rpc.copyParams".to_string(), reserved)];
+    match handler.register_methods(methods) {
+        Err(ErrorVariant::ReservedMethodSignature(s)) => assert_eq!(s, "rpc.copyParams"),
+        _ => panic!("The reserved signature was registered"),
+    }
+    assert_eq!(handler.method_count().unwrap(), 5);
+}
+
+/// Answer with the string context of the message, or `null` without one
+struct EchoContext;
+
+impl<'a> JrpcMethodTrait<'a> for EchoContext {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        self.generate_future_with_context(request, JrpcContext::default())
+    }
+
+    fn generate_future_with_context(
+        &self,
+        request: JrpcRequest,
+        context: JrpcContext,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let future = futures_jsonrpc::futures::future::lazy(move || {
+            let address = context.get::<String>().map(|a| JsonValue::from(a.as_str()));
+            let result = JrpcResponseParam::generate_result(address.unwrap_or(JsonValue::Null))?;
+            request.generate_response(result).map(Some)
+        });
+
+        Ok(Box::new(future))
+    }
+}
+
+#[test]
+fn methods_receive_the_context_of_the_message() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/echoContext", EchoContext)
+        .unwrap();
+
+    let message = r#"{"jsonrpc": "2.0", "method": "some/echoContext", "id": 1}"#;
+    let response = handler
+        .handle_message_with_context(message, "127.0.0.1:4000".to_string())
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        response.get_result(),
+        &Some(JsonValue::from("127.0.0.1:4000"))
+    );
+
+    let response = call(&handler, "some/echoContext", JsonValue::Null).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::Null));
+}
+
+#[test]
+fn streams_of_messages_are_answered_in_order() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let input = concat!(
+        r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 1, "id": 1}"#,
+        "\n",
+        r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 2}"#,
+        "\n\n",
+        r#"{"jsonrpc": "2.0", "method": "some/unknown", "id": 3}"#,
+        "\n",
+    );
+    let lines = input.lines().map(str::to_string).collect::<Vec<String>>();
+    let messages = futures_jsonrpc::futures::stream::iter_ok(lines);
+
+    let responses = handler.serve_stream(messages).collect().wait().unwrap();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(
+        responses[0].as_ref().unwrap().get_result(),
+        &Some(JsonValue::from(1))
+    );
+    assert!(responses[1].is_none());
+
+    let error = responses[2].as_ref().unwrap();
+    assert_eq!(error.get_error().as_ref().unwrap().get_code(), &-32601);
+    assert_eq!(error.get_id(), &JsonValue::from(3));
+}
+
+/// Fail every request, either while generating the future or while polling it
+struct Fails(bool);
+
+impl<'a> JrpcMethodTrait<'a> for Fails {
+    fn generate_future(
+        &self,
+        _request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        match self.0 {
+            true => Err(ErrorVariant::InternalError),
+            false => Ok(Box::new(futures_jsonrpc::futures::future::err(
+                ErrorVariant::InternalError,
+            ))),
+        }
+    }
+}
+
+#[test]
+fn caught_method_errors_are_answered_with_the_request_id() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/fails", Fails(false))
+        .unwrap()
+        .register_method_catching_errors("some/failsCaught", Fails(false))
+        .unwrap()
+        .register_method_catching_errors("some/failsEarlyCaught", Fails(true))
+        .unwrap();
+
+    let message = |method: &str, id: &str| {
+        format!(
+            r#"{{"jsonrpc": "2.0", "method": "{}", "id": {}}}"#,
+            method, id
+        )
+    };
+
+    match handler.handle_message_blocking(message("some/fails", "1")) {
+        Err(ErrorVariant::InternalError) => (),
+        r => panic!("Unexpected outcome {:?}", r),
+    }
+
+    for method in &["some/failsCaught", "some/failsEarlyCaught"] {
+        let response = handler
+            .handle_message_blocking(message(method, r#""a""#))
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.get_id(), &JsonValue::from("a"));
+        assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32603);
+    }
+
+    let notification = r#"{"jsonrpc": "2.0", "method": "some/failsCaught"}"#;
+    assert!(handler
+        .handle_message_blocking(notification)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn legacy_versions_are_accepted_when_lenient() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let message = r#"{"jsonrpc": "2", "method": "some/copyParams", "params": 1, "id": 1}"#;
+    assert!(handler.handle_message_blocking(message).is_err());
+
+    handler.set_lenient_version(true).unwrap();
+    let response = handler.handle_message_blocking(message).unwrap().unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+
+    let batch = r#"[{"method": "some/copyParams", "params": 2, "id": 2}]"#;
+    match handler.handle_batch_message(batch).unwrap().wait().unwrap() {
+        Some(JrpcBatchOutcome::Batch(batch)) => {
+            let result = batch.get_responses()[0].get_result();
+            assert_eq!(result, &Some(JsonValue::from(2)));
+        }
+        r => panic!("Unexpected outcome {:?}", r),
+    }
+}
+
+/// Count the notifications received
+struct CountNotifications(Arc<Mutex<usize>>);
+
+impl<'a> JrpcNotificationMethodTrait<'a> for CountNotifications {
+    fn generate_future(
+        &self,
+        _request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = (), Error = ErrorVariant>>, ErrorVariant> {
+        *self.0.lock().unwrap() += 1;
+        Ok(Box::new(futures_jsonrpc::futures::future::ok(())))
+    }
+}
+
+#[test]
+fn notification_methods_never_answer() {
+    let handler = JrpcHandler::new().unwrap();
+    let count = Arc::new(Mutex::new(0));
+    handler
+        .register_notification("log/event", CountNotifications(count.clone()))
+        .unwrap();
+
+    let notification = r#"{"jsonrpc": "2.0", "method": "log/event", "params": ["a"]}"#;
+    assert!(handler
+        .handle_message_blocking(notification)
+        .unwrap()
+        .is_none());
+    assert_eq!(*count.lock().unwrap(), 1);
+
+    let call = r#"{"jsonrpc": "2.0", "method": "log/event", "params": ["a"], "id": 4}"#;
+    let response = handler.handle_message_blocking(call).unwrap().unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32600);
+    assert_eq!(response.get_id(), &JsonValue::from(4));
+    assert_eq!(*count.lock().unwrap(), 1);
+}
+
+#[test]
+fn only_requests_without_an_id_are_notifications() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let absent = r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 1}"#;
+    let null = r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 1, "id": null}"#;
+    let number = r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": 1, "id": 2}"#;
+
+    for preserve in &[false, true] {
+        handler.set_preserve_raw_ids(*preserve).unwrap();
+
+        let request = JrpcRequest::parse(absent).unwrap();
+        assert!(request.is_notification());
+        assert_eq!(request.get_id(), &None);
+        assert!(handler.handle_message_blocking(absent).unwrap().is_none());
+
+        let request = JrpcRequest::parse_preserving_id(null).unwrap();
+        assert!(!request.is_notification());
+        assert_eq!(request.get_id(), &Some(JsonValue::Null));
+        let response = handler.handle_message_blocking(null).unwrap().unwrap();
+        assert_eq!(response.get_id(), &JsonValue::Null);
+        assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+
+        let request = JrpcRequest::parse(number).unwrap();
+        assert!(!request.is_notification());
+        assert_eq!(request.id_as_i64(), Some(2));
+        let response = handler.handle_message_blocking(number).unwrap().unwrap();
+        assert_eq!(response.id_as_i64(), Some(2));
+    }
+
+    // The `null` id survives a round trip, distinct from an absent id
+    let request = JrpcRequest::parse(null).unwrap();
+    assert_eq!(JrpcRequest::parse(request.to_string()).unwrap(), request);
+    assert!(!JrpcRequest::parse(absent)
+        .unwrap()
+        .to_string()
+        .contains("id"));
+}
@@ -0,0 +1,273 @@
+use futures_jsonrpc::futures::executor::{self, Notify};
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::futures::{future, task};
+use futures_jsonrpc::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+generate_method_with_data_and_future!(
+    WaitForFlag,
+    Arc<AtomicBool>,
+    impl Future for WaitForFlag {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            if !self.get_data().load(Ordering::SeqCst) {
+                return Ok(Async::NotReady);
+            }
+
+            let request = self.get_request()?;
+            let message = JrpcResponseParam::generate_result(JsonValue::Null)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+generate_method_with_data_and_future!(
+    RecordParams,
+    Arc<Mutex<Vec<JsonValue>>>,
+    impl Future for RecordParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+            self.get_data().lock().unwrap().push(params);
+
+            let message = JrpcResponseParam::generate_result(JsonValue::Null)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+struct Flag(AtomicBool);
+
+impl Notify for Flag {
+    fn notify(&self, _id: usize) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn await_idle_resolves_after_every_dispatch() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/wait", WaitForFlag::new(flag.clone()).unwrap())
+        .unwrap();
+
+    let futures: Vec<_> = (0..3)
+        .map(|id| {
+            let message = format!(
+                r#"{{"jsonrpc": "2.0", "method": "some/wait", "id": {}}}"#,
+                id
+            );
+            handler.handle_message(message).unwrap()
+        })
+        .collect();
+    assert_eq!(handler.inflight_count().unwrap(), 3);
+
+    let notified = Arc::new(Flag(AtomicBool::new(false)));
+    let mut idle = executor::spawn(handler.await_idle());
+    assert!(idle
+        .poll_future_notify(&notified, 0)
+        .unwrap()
+        .is_not_ready());
+
+    flag.store(true, Ordering::SeqCst);
+    for future in futures {
+        assert!(future.wait().unwrap().is_some());
+    }
+
+    assert!(notified.0.load(Ordering::SeqCst));
+    assert_eq!(handler.inflight_count().unwrap(), 0);
+    assert!(idle.wait_future().is_ok());
+}
+
+#[test]
+fn dropped_dispatches_are_not_in_flight() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method(
+            "some/wait",
+            WaitForFlag::new(Arc::new(AtomicBool::new(false))).unwrap(),
+        )
+        .unwrap();
+
+    let future = handler
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/wait", "id": 1}"#)
+        .unwrap();
+    assert_eq!(handler.inflight_count().unwrap(), 1);
+
+    drop(future);
+    assert_eq!(handler.inflight_count().unwrap(), 0);
+    assert!(handler.await_idle().wait().is_ok());
+}
+
+fn bounded_handler(
+    flag: Arc<AtomicBool>,
+    dispatched: Arc<Mutex<Vec<JsonValue>>>,
+) -> JrpcHandler<'static> {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .set_max_inflight(Some(1))
+        .unwrap()
+        .register_method("some/wait", WaitForFlag::new(flag).unwrap())
+        .unwrap()
+        .register_method("some/bulk", RecordParams::new(dispatched.clone()).unwrap())
+        .unwrap()
+        .register_method_with_priority("some/health", RecordParams::new(dispatched).unwrap(), 10)
+        .unwrap();
+    handler
+}
+
+fn send(handler: &JrpcHandler<'static>, method: &str, params: i32) -> impl Future {
+    let message = format!(
+        r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}, "id": {}}}"#,
+        method, params, params
+    );
+    handler.handle_message(message).unwrap()
+}
+
+#[test]
+fn queued_requests_are_dispatched_by_priority() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let dispatched = Arc::new(Mutex::new(vec![]));
+    let handler = bounded_handler(flag.clone(), dispatched.clone());
+
+    let wait = send(&handler, "some/wait", 0);
+    let bulk_1 = send(&handler, "some/bulk", 1);
+    let bulk_2 = send(&handler, "some/bulk", 2);
+    let health = send(&handler, "some/health", 3);
+    assert_eq!(handler.inflight_count().unwrap(), 1);
+
+    let notified = Arc::new(Flag(AtomicBool::new(false)));
+    let mut bulk_1 = executor::spawn(bulk_1);
+    assert!(bulk_1.poll_future_notify(&notified, 0).is_ok());
+    assert!(dispatched.lock().unwrap().is_empty());
+
+    flag.store(true, Ordering::SeqCst);
+    assert!(wait.wait().is_ok());
+
+    // The slot went to the high priority request, so the bulk one is still queued
+    assert!(bulk_1
+        .poll_future_notify(&notified, 0)
+        .map(|a| a.is_not_ready())
+        .unwrap_or(false));
+    assert!(health.wait().is_ok());
+    assert!(notified.0.load(Ordering::SeqCst));
+
+    assert!(bulk_1.wait_future().is_ok());
+    assert!(bulk_2.wait().is_ok());
+    assert_eq!(
+        *dispatched.lock().unwrap(),
+        vec![JsonValue::from(3), JsonValue::from(1), JsonValue::from(2)]
+    );
+    assert_eq!(handler.inflight_count().unwrap(), 0);
+}
+
+#[test]
+fn dropped_queued_requests_release_their_slot() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let dispatched = Arc::new(Mutex::new(vec![]));
+    let handler = bounded_handler(flag.clone(), dispatched.clone());
+
+    let wait = send(&handler, "some/wait", 0);
+    let dropped_early = send(&handler, "some/health", 1);
+    let granted = send(&handler, "some/bulk", 2);
+    drop(dropped_early);
+
+    flag.store(true, Ordering::SeqCst);
+    assert!(wait.wait().is_ok());
+    assert_eq!(handler.inflight_count().unwrap(), 1);
+
+    // Granted a slot, but dropped before being dispatched
+    drop(granted);
+    assert_eq!(handler.inflight_count().unwrap(), 0);
+    assert!(dispatched.lock().unwrap().is_empty());
+
+    assert!(send(&handler, "some/bulk", 3).wait().is_ok());
+    assert_eq!(*dispatched.lock().unwrap(), vec![JsonValue::from(3)]);
+}
+
+/// Count the futures running at once, each yielding once before resolving
+#[derive(Default)]
+struct CountRunning {
+    running: Arc<AtomicUsize>,
+    max: Arc<AtomicUsize>,
+}
+
+impl<'a> JrpcMethodTrait<'a> for CountRunning {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let running = self.running.clone();
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max.fetch_max(now, Ordering::SeqCst);
+
+        let mut yielded = false;
+        Ok(Box::new(future::poll_fn(move || {
+            if !yielded {
+                yielded = true;
+                task::current().notify();
+                return Ok(Async::NotReady);
+            }
+
+            running.fetch_sub(1, Ordering::SeqCst);
+            let message = JrpcResponseParam::generate_result(JsonValue::Null)
+                .and_then(|result| request.generate_response(result))?;
+            Ok(Async::Ready(Some(message)))
+        })))
+    }
+}
+
+#[test]
+fn batch_concurrency_is_bounded() {
+    let counter = CountRunning::default();
+    let max = counter.max.clone();
+    let handler = JrpcHandler::new().unwrap();
+    handler.register_method("some/count", counter).unwrap();
+
+    let batch: Vec<_> = (0..50)
+        .map(|id| {
+            format!(
+                r#"{{"jsonrpc": "2.0", "method": "some/count", "id": {}}}"#,
+                id
+            )
+        })
+        .collect();
+    let batch = format!("[{}]", batch.join(","));
+
+    let responses = match handler
+        .handle_batch_message_with_concurrency(&batch, 3)
+        .and_then(|future| future.wait())
+        .unwrap()
+    {
+        Some(JrpcBatchOutcome::Batch(batch)) => batch,
+        outcome => panic!("Unexpected outcome {:?}", outcome),
+    };
+
+    let ids: Vec<_> = responses
+        .get_responses()
+        .iter()
+        .map(|r| r.get_id().clone())
+        .collect();
+    assert_eq!(ids, (0..50).map(JsonValue::from).collect::<Vec<_>>());
+    assert_eq!(max.load(Ordering::SeqCst), 3);
+
+    // Without a limit, the whole batch runs at once
+    max.store(0, Ordering::SeqCst);
+    handler
+        .handle_batch_message(&batch)
+        .and_then(|future| future.wait())
+        .unwrap();
+    assert_eq!(max.load(Ordering::SeqCst), 50);
+}
@@ -0,0 +1,63 @@
+use futures_jsonrpc::*;
+
+#[test]
+fn valid_messages_have_no_issues() {
+    assert!(
+        lint_request(r#"{"jsonrpc": "2.0", "method": "a", "params": [1], "id": 1}"#).is_empty()
+    );
+    assert!(lint_request(r#"{"jsonrpc": "2.0", "method": "a"}"#).is_empty());
+    assert!(lint_response(r#"{"jsonrpc": "2.0", "result": null, "id": "1"}"#).is_empty());
+    assert!(lint_response(
+        r#"{"jsonrpc": "2.0", "error": {"code": -32600, "message": "Invalid"}, "id": null}"#
+    )
+    .is_empty());
+}
+
+#[test]
+fn every_request_violation_is_reported() {
+    let issues = lint_request(r#"{"jsonrpc": "1.0", "method": 3, "params": "a", "id": [1]}"#);
+
+    assert_eq!(
+        issues,
+        vec![
+            LintIssue::InvalidVersion(Some(JsonValue::from("1.0"))),
+            LintIssue::InvalidMethod(Some(JsonValue::from(3))),
+            LintIssue::InvalidParams(JsonValue::from("a")),
+            LintIssue::InvalidId(serde_json::json!([1])),
+        ]
+    );
+}
+
+#[test]
+fn every_response_violation_is_reported() {
+    let issues = lint_response(r#"{"result": 1, "error": {"code": "a"}}"#);
+
+    assert_eq!(
+        issues,
+        vec![
+            LintIssue::InvalidVersion(None),
+            LintIssue::ResultAndError,
+            LintIssue::InvalidError("'code' must be an integer".to_string()),
+            LintIssue::InvalidError("'message' must be a string".to_string()),
+            LintIssue::MissingId,
+        ]
+    );
+
+    assert_eq!(
+        lint_response(r#"{"jsonrpc": "2.0", "id": {}}"#),
+        vec![
+            LintIssue::MissingResultAndError,
+            LintIssue::InvalidId(serde_json::json!({})),
+        ]
+    );
+}
+
+#[test]
+fn unparseable_messages_are_reported_once() {
+    match lint_request("{").as_slice() {
+        [LintIssue::InvalidJson(_)] => (),
+        other => panic!("Unexpected issues {:?}", other),
+    }
+
+    assert_eq!(lint_response("[]"), vec![LintIssue::NotAnObject]);
+}
@@ -0,0 +1,190 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::mock::{
+    loopback, request_for_response, round_trip, validate_transcript, Direction, TranscriptError,
+};
+use futures_jsonrpc::*;
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+#[test]
+fn round_trip_resolves_the_response() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let request = JrpcRequest::prepare_to_send_request("some/copyParams", Some(42.into())).unwrap();
+    let response = round_trip(&handler, &request).unwrap().unwrap();
+
+    assert_eq!(response.get_result(), &Some(JsonValue::from(42)));
+    assert_eq!(Some(response.get_id()), request.get_id().as_ref());
+}
+
+#[test]
+fn golden_response_is_reproduced() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let golden =
+        JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": [1, 2], "id": "golden"}"#).unwrap();
+    let request =
+        request_for_response(&golden, "some/copyParams", Some(serde_json::json!([1, 2]))).unwrap();
+    let response = round_trip(&handler, &request).unwrap().unwrap();
+
+    assert_eq!(response.get_result(), golden.get_result());
+    assert_eq!(response.get_id(), golden.get_id());
+}
+
+#[test]
+fn compliant_transcript_is_accepted() {
+    let transcript = vec![
+        (
+            Direction::ClientToServer,
+            r#"{"jsonrpc": "2.0", "method": "a", "id": 1}"#,
+        ),
+        (
+            Direction::ClientToServer,
+            r#"{"jsonrpc": "2.0", "method": "b"}"#,
+        ),
+        (
+            Direction::ClientToServer,
+            r#"[{"jsonrpc": "2.0", "method": "a", "id": "x"}, {"jsonrpc": "2.0", "method": "b"}]"#,
+        ),
+        (
+            Direction::ServerToClient,
+            r#"[{"jsonrpc": "2.0", "result": 2, "id": "x"}]"#,
+        ),
+        (
+            Direction::ServerToClient,
+            r#"{"jsonrpc": "2.0", "result": 1, "id": 1}"#,
+        ),
+        (Direction::ClientToServer, "{"),
+        (
+            Direction::ServerToClient,
+            r#"{"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error", "data": null}, "id": null}"#,
+        ),
+    ];
+
+    match validate_transcript(&transcript) {
+        Err(errors) => {
+            assert_eq!(errors.len(), 1);
+            match &errors[0] {
+                TranscriptError::InvalidMessage(5, ErrorVariant::JsonParseError(_)) => (),
+                other => panic!("Unexpected error {:?}", other),
+            }
+        }
+        Ok(()) => panic!("The broken message was accepted"),
+    }
+
+    assert!(validate_transcript(&transcript[..5]).is_ok());
+}
+
+#[test]
+fn transcripts_accept_null_results() {
+    let transcript = vec![
+        (
+            Direction::ClientToServer,
+            r#"{"jsonrpc": "2.0", "method": "a", "id": 1}"#.to_string(),
+        ),
+        (
+            Direction::ServerToClient,
+            r#"{"jsonrpc":"2.0","result":null,"id":1}"#.to_string(),
+        ),
+    ];
+
+    assert!(validate_transcript(&transcript).is_ok());
+}
+
+#[test]
+fn transcript_violations_are_all_reported() {
+    let transcript = vec![
+        (
+            Direction::ClientToServer,
+            r#"{"jsonrpc": "2.0", "method": "a", "id": 1}"#.to_string(),
+        ),
+        (
+            Direction::ClientToServer,
+            r#"{"jsonrpc": "2.0", "method": "a", "id": 1}"#.to_string(),
+        ),
+        (
+            Direction::ClientToServer,
+            r#"{"jsonrpc": "2.0", "method": "a", "id": 2}"#.to_string(),
+        ),
+        (
+            Direction::ClientToServer,
+            r#"{"jsonrpc": "2.0", "method": "a", "id": {}}"#.to_string(),
+        ),
+        (
+            Direction::ServerToClient,
+            r#"{"jsonrpc": "2.0", "result": 1, "id": 3}"#.to_string(),
+        ),
+        (
+            Direction::ServerToClient,
+            r#"{"jsonrpc": "2.0", "result": 1, "id": 1}"#.to_string(),
+        ),
+    ];
+
+    let errors = validate_transcript(&transcript).unwrap_err();
+    assert_eq!(errors.len(), 4);
+
+    match &errors[..] {
+        [TranscriptError::DuplicateRequestId(1, id), TranscriptError::InvalidMessage(3, ErrorVariant::InvalidJsonRpcId), TranscriptError::UnexpectedResponse(4, unexpected), TranscriptError::MissingResponse(2, missing)] =>
+        {
+            assert_eq!(id, &JsonValue::from(1));
+            assert_eq!(unexpected, &JsonValue::from(3));
+            assert_eq!(missing, &JsonValue::from(2));
+        }
+        other => panic!("Unexpected errors {:?}", other),
+    }
+}
+
+#[test]
+fn loopback_client_calls_the_handler() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+    let client = loopback(handler);
+
+    let response = client
+        .call("some/copyParams", Some(serde_json::json!([1, 2])))
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(serde_json::json!([1, 2])));
+
+    // Without params the method answers a `null` result
+    let response = client
+        .call("some/copyParams", None)
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::Null));
+
+    let response = client.call("some/missing", None).unwrap().wait().unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32601);
+
+    let notification = JrpcRequest::prepare_to_send_notification("some/copyParams", None).unwrap();
+    match client.send_request(notification).unwrap().wait() {
+        Err(ErrorVariant::NoResponse(id)) => assert!(id.is_null()),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,91 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::openrpc::{MethodSchema, OPENRPC_VERSION};
+use futures_jsonrpc::*;
+use serde_json::json;
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+fn add_schema() -> MethodSchema {
+    MethodSchema::new()
+        .with_summary("Add two numbers")
+        .with_param("a", json!({"type": "integer"}))
+        .with_param("b", json!({"type": "integer"}))
+        .with_result("sum", json!({"type": "integer"}))
+}
+
+#[test]
+fn document_lists_registered_methods_with_their_schemas() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("math/add", CopyParams::new().unwrap())
+        .unwrap()
+        .register_method("echo", CopyParams::new().unwrap())
+        .unwrap()
+        .register_system_method("rpc.discover", CopyParams::new().unwrap())
+        .unwrap()
+        .set_method_schema("math/add", add_schema())
+        .unwrap();
+
+    let document = handler.to_openrpc_document().unwrap();
+    assert_eq!(document["openrpc"], JsonValue::from(OPENRPC_VERSION));
+
+    let methods = document["methods"].as_array().unwrap();
+    assert_eq!(
+        methods[0],
+        json!({"name": "echo", "params": [], "result": {"name": "result", "schema": {}}})
+    );
+    assert_eq!(
+        methods[1],
+        json!({
+            "name": "math/add",
+            "summary": "Add two numbers",
+            "params": [
+                {"name": "a", "schema": {"type": "integer"}},
+                {"name": "b", "schema": {"type": "integer"}},
+            ],
+            "result": {"name": "sum", "schema": {"type": "integer"}},
+        })
+    );
+    assert_eq!(methods.len(), 2);
+
+    // Method objects read back into the schemas they were built from
+    let parsed: MethodSchema = serde_json::from_value(methods[1].clone()).unwrap();
+    assert_eq!(parsed, add_schema());
+}
+
+#[test]
+fn schemas_are_dropped_with_their_methods() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("math/add", CopyParams::new().unwrap())
+        .unwrap()
+        .set_method_schema("math/add", add_schema())
+        .unwrap();
+
+    assert!(handler.unregister_method("math/add").unwrap());
+    handler
+        .register_method("math/add", CopyParams::new().unwrap())
+        .unwrap();
+
+    let document = handler.to_openrpc_document().unwrap();
+    assert_eq!(
+        serde_json::from_value::<MethodSchema>(document["methods"][0].clone()).unwrap(),
+        MethodSchema::new()
+    );
+}
@@ -0,0 +1,219 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::params::{named_to_positional, positional_to_named, ParamsSpec};
+use futures_jsonrpc::*;
+use serde_json::json;
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let message = JrpcResponseParam::generate_result(request.params_or_null())
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+const ORDER: &[&str] = &["minuend", "subtrahend"];
+
+#[test]
+fn named_params_become_positional() {
+    let params = json!({"subtrahend": 23, "minuend": 42});
+    assert_eq!(named_to_positional(params, ORDER).unwrap(), json!([42, 23]));
+}
+
+#[test]
+fn positional_params_become_named() {
+    let params = json!([42, 23]);
+    assert_eq!(
+        positional_to_named(params, ORDER).unwrap(),
+        json!({"minuend": 42, "subtrahend": 23})
+    );
+}
+
+#[test]
+fn missing_and_unexpected_names_are_rejected() {
+    match named_to_positional(json!({"minuend": 42}), ORDER) {
+        Err(ErrorVariant::InvalidParams(s)) => assert!(s.contains("subtrahend")),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match named_to_positional(json!({"minuend": 42, "subtrahend": 23, "x": 1}), ORDER) {
+        Err(ErrorVariant::InvalidParams(s)) => assert!(s.contains("'x'")),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match named_to_positional(json!([42, 23]), ORDER) {
+        Err(ErrorVariant::InvalidParams(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn omitted_trailing_params_are_accepted_and_extras_rejected() {
+    assert_eq!(
+        positional_to_named(json!([42]), ORDER).unwrap(),
+        json!({"minuend": 42})
+    );
+    assert_eq!(positional_to_named(json!([]), ORDER).unwrap(), json!({}));
+
+    match positional_to_named(json!([42, 23, 1]), ORDER) {
+        Err(ErrorVariant::InvalidParams(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+mod subtract {
+    use futures_jsonrpc::futures::prelude::*;
+    use futures_jsonrpc::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct SubtractParams {
+        minuend: i64,
+        subtrahend: i64,
+    }
+
+    generate_method!(
+        Subtract,
+        impl Future for Subtract {
+            type Item = Option<JrpcResponse>;
+            type Error = ErrorVariant;
+
+            fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+                let request = self.get_request()?;
+                let params: SubtractParams =
+                    request.get_params_with_order(&["minuend", "subtrahend"])?;
+
+                let result = JsonValue::from(params.minuend - params.subtrahend);
+                let message = JrpcResponseParam::generate_result(result)
+                    .and_then(|result| request.generate_response(result))?;
+
+                Ok(Async::Ready(Some(message)))
+            }
+        }
+    );
+
+    fn subtract(params: &str) -> Result<Option<JrpcResponse>, ErrorVariant> {
+        let handler = JrpcHandler::new().unwrap();
+        handler
+            .register_method("subtract", Subtract::new().unwrap())
+            .unwrap();
+
+        let message = format!(
+            r#"{{"jsonrpc": "2.0", "method": "subtract", "params": {}, "id": 1}}"#,
+            params
+        );
+        handler
+            .handle_message(message)
+            .and_then(|future| future.wait())
+    }
+
+    #[test]
+    fn same_method_accepts_both_param_shapes() {
+        let positional = subtract("[42, 23]").unwrap().unwrap();
+        let named = subtract(r#"{"subtrahend": 23, "minuend": 42}"#)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(positional.get_result(), &Some(JsonValue::from(19)));
+        assert_eq!(positional.get_result(), named.get_result());
+    }
+
+    #[test]
+    fn bad_params_are_invalid_for_both_shapes() {
+        for params in &["[42]", r#"{"minuend": 42}"#] {
+            let error = subtract(params).unwrap_err();
+            assert_eq!(JrpcError::from(error).get_code(), &-32602);
+        }
+    }
+}
+
+#[test]
+fn positional_and_named_params_are_accessed_in_place() {
+    let positional =
+        JrpcRequest::new("subtract", Some(json!([42, 23])), Some(JsonValue::from(1))).unwrap();
+    assert_eq!(positional.params_as_array().map(Vec::len), Some(2));
+    assert!(positional.params_as_object().is_none());
+    assert_eq!(positional.param_at(1), Some(&JsonValue::from(23)));
+    assert_eq!(positional.param_at(2), None);
+    assert_eq!(positional.param_named("subtrahend"), None);
+
+    let named = JrpcRequest::new(
+        "subtract",
+        Some(json!({"subtrahend": 23, "minuend": 42})),
+        Some(JsonValue::from(2)),
+    )
+    .unwrap();
+    assert!(named.params_as_array().is_none());
+    assert_eq!(named.params_as_object().map(|p| p.len()), Some(2));
+    assert_eq!(named.param_named("minuend"), Some(&JsonValue::from(42)));
+    assert_eq!(named.param_named("other"), None);
+    assert_eq!(named.param_at(0), None);
+
+    let absent = JrpcRequest::new("subtract", None, Some(JsonValue::from(3))).unwrap();
+    assert!(absent.params_as_array().is_none());
+    assert!(absent.params_as_object().is_none());
+    assert_eq!(absent.param_at(0), None);
+    assert_eq!(absent.param_named("minuend"), None);
+}
+
+#[test]
+fn absent_params_default_to_null_or_are_required() {
+    let present = JrpcRequest::parse(r#"{"jsonrpc": "2.0", "method": "a", "params": [1]}"#);
+    let present = present.unwrap();
+    assert_eq!(present.params_or_null(), json!([1]));
+    assert_eq!(present.require_params().unwrap(), &json!([1]));
+
+    let absent = JrpcRequest::parse(r#"{"jsonrpc": "2.0", "method": "a"}"#).unwrap();
+    let null = JrpcRequest::parse(r#"{"jsonrpc": "2.0", "method": "a", "params": null}"#);
+    let null = null.unwrap();
+
+    for request in &[absent, null] {
+        assert_eq!(request.params_or_null(), JsonValue::Null);
+        match request.require_params() {
+            Err(ErrorVariant::InvalidParams(_)) => (),
+            r => panic!("Unexpected outcome {:?}", r),
+        }
+    }
+}
+
+#[test]
+fn params_are_checked_against_the_spec_of_the_method() {
+    let handler = JrpcHandler::new().unwrap();
+    let copy = || CopyParams::new().unwrap();
+    handler
+        .register_method_with_schema("math/pair", copy(), ParamsSpec::arity(2))
+        .unwrap()
+        .register_method_with_schema("math/named", copy(), ParamsSpec::keys(["minuend"]))
+        .unwrap();
+
+    let call = |method: &str, params: JsonValue| {
+        let request = JrpcRequest::new(method, Some(params), Some(JsonValue::from(1))).unwrap();
+        handler
+            .handle_request(request)
+            .and_then(|future| future.wait())
+            .unwrap()
+            .unwrap()
+    };
+
+    let response = call("math/pair", json!([1, 2]));
+    assert_eq!(response.get_result(), &Some(json!([1, 2])));
+
+    let response = call("math/pair", json!([1]));
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32602);
+    assert_eq!(response.get_id(), &JsonValue::from(1));
+
+    assert!(call("math/named", json!({"minuend": 1}))
+        .get_error()
+        .is_none());
+    let response = call("math/named", json!({"subtrahend": 1}));
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32602);
+    let response = call("math/named", json!([1]));
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32602);
+}
@@ -0,0 +1,973 @@
+use futures_jsonrpc::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Summary {
+    total: u64,
+}
+
+#[test]
+fn lazy_response_deserializes_only_requested_fields() {
+    let items: Vec<String> = (0..1000).map(|i| format!("item-{}", i)).collect();
+    let message = serde_json::json!({
+        "jsonrpc": "2.0",
+        "result": {"total": 1000, "items": items},
+        "id": 7
+    })
+    .to_string();
+
+    let response = JrpcResponse::parse_lazy(message.as_str()).unwrap();
+    assert!(response
+        .get_raw_result()
+        .unwrap()
+        .get()
+        .contains("item-999"));
+
+    let summary: Summary = response.get_result_as().unwrap().unwrap();
+    assert_eq!(summary.total, 1000);
+    assert_eq!(response.get_id(), &JsonValue::from(7));
+
+    let response = response.into_response().unwrap();
+    assert_eq!(response.get_result().as_ref().unwrap()["total"], 1000);
+}
+
+#[test]
+fn lazy_response_keeps_errors() {
+    let message = r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found", "data": null}, "id": "a"}"#;
+
+    let response = JrpcResponse::parse_lazy(message).unwrap();
+    assert!(response.get_raw_result().is_none());
+    assert!(response.get_result_as::<Summary>().unwrap().is_none());
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32601);
+}
+
+#[test]
+fn lazy_response_is_validated() {
+    let message = r#"{"jsonrpc": "1.0", "result": 1, "id": 1}"#;
+
+    match JrpcResponse::parse_lazy(message) {
+        Err(ErrorVariant::InvalidJsonRpcVersion) => (),
+        other => panic!("Unexpected parse result {:?}", other),
+    }
+}
+
+fn request_with_params(params: JsonValue) -> JrpcRequest {
+    JrpcRequest::new("some/list", Some(params), Some(JsonValue::from(1))).unwrap()
+}
+
+#[test]
+fn param_or_falls_back_to_the_default() {
+    let request = request_with_params(serde_json::json!({"offset": 5}));
+    assert_eq!(request.param_or::<i64>("limit", 100).unwrap(), 100);
+
+    let request = JrpcRequest::new("some/list", None, Some(JsonValue::from(1))).unwrap();
+    assert_eq!(request.param_or::<i64>("limit", 100).unwrap(), 100);
+}
+
+#[test]
+fn param_or_returns_the_present_value() {
+    let request = request_with_params(serde_json::json!({"limit": 20}));
+    assert_eq!(request.param_or::<i64>("limit", 100).unwrap(), 20);
+    assert_eq!(request.param::<i64>("limit").unwrap(), 20);
+}
+
+#[test]
+fn param_or_rejects_a_mistyped_value() {
+    let request = request_with_params(serde_json::json!({"limit": "twenty"}));
+
+    match request.param_or::<i64>("limit", 100) {
+        Err(e @ ErrorVariant::InvalidParams(_)) => {
+            assert_eq!(JrpcError::from(e).get_code(), &-32602)
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match request.param::<i64>("offset") {
+        Err(ErrorVariant::InvalidParams(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn batch_request_serializes_as_an_array() {
+    let call = JrpcRequest::new("some/call", Some(serde_json::json!([1])), Some(1.into())).unwrap();
+    let notification =
+        JrpcRequest::prepare_to_send_notification("some/notify", Some(serde_json::json!({"a": 1})))
+            .unwrap();
+
+    let batch = JrpcBatchRequest::new(vec![call, notification]).unwrap();
+    let value = serde_json::to_value(&batch).unwrap();
+
+    assert_eq!(
+        value,
+        serde_json::json!([
+            {"jsonrpc": "2.0", "method": "some/call", "params": [1], "id": 1},
+            {"jsonrpc": "2.0", "method": "some/notify", "params": {"a": 1}}
+        ])
+    );
+}
+
+#[test]
+fn empty_batch_request_is_rejected() {
+    match JrpcBatchRequest::new(vec![]) {
+        Err(ErrorVariant::EmptyBatch) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn nested_errors_are_packed_in_data() {
+    let errors = vec![
+        JrpcError::new(-32602, "Invalid 'limit'", Some(JsonValue::from("limit"))),
+        JrpcError::from(JrpcErrorEnum::InternalError),
+    ];
+    let error = JrpcError::with_nested_errors(-32602, "Several params are invalid", errors);
+
+    assert_eq!(error.get_code(), &-32602);
+    assert_eq!(
+        error.get_data(),
+        &Some(serde_json::json!([
+            {"code": -32602, "message": "Invalid 'limit'", "data": "limit"},
+            {"code": -32603, "message": "Internal JSON-RPC error.", "data": null}
+        ]))
+    );
+
+    let parsed = JrpcError::parse(serde_json::to_string(&error).unwrap()).unwrap();
+    assert_eq!(parsed.get_data(), error.get_data());
+}
+
+#[test]
+fn flat_value_of_a_success_is_the_result() {
+    let response =
+        JrpcResponse::new(Some(serde_json::json!({"a": [1, 2]})), None, 1.into()).unwrap();
+    assert_eq!(response.into_flat_value(), serde_json::json!({"a": [1, 2]}));
+}
+
+#[test]
+fn flat_value_of_a_failure_is_the_error_object() {
+    let error = JrpcError::new(-32602, "Invalid params", Some("limit".into()));
+    let response = JrpcResponse::new(None, Some(error), "a".into()).unwrap();
+    assert_eq!(
+        response.into_flat_value(),
+        serde_json::json!({"code": -32602, "message": "Invalid params", "data": "limit"})
+    );
+}
+
+#[test]
+fn application_error_accepts_application_codes() {
+    let error = JrpcError::application_error(1001, "Account locked", Some("alice".into())).unwrap();
+    assert_eq!(error.get_code(), &1001);
+    assert_eq!(error.get_message(), "Account locked");
+
+    assert!(JrpcError::application_error(-31999, "Just outside", None).is_ok());
+    assert!(JrpcError::application_error(-32769, "Just outside", None).is_ok());
+}
+
+#[test]
+fn application_error_rejects_reserved_codes() {
+    for code in &[-32001, -32000, -32601, -32768] {
+        match JrpcError::application_error(*code, "Reserved", None) {
+            Err(ErrorVariant::InvalidErrorCode(c)) => assert_eq!(c, *code),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn error_with_request_id_echoes_the_id() {
+    let error = JrpcError::from(JrpcErrorEnum::MethodNotFound);
+
+    for id in &[Some(JsonValue::from("a")), Some(JsonValue::from(7))] {
+        let request = JrpcRequest::new("some/missing", None, id.clone()).unwrap();
+        let response = JrpcResponse::error_with_request_id(error.clone(), &request);
+
+        assert_eq!(response.get_id(), id.as_ref().unwrap());
+        assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32601);
+        assert!(response.get_result().is_none());
+    }
+
+    let notification = JrpcRequest::new("some/missing", None, None).unwrap();
+    let response = JrpcResponse::error_with_request_id(error, &notification);
+    assert_eq!(response.get_id(), &JsonValue::Null);
+}
+
+#[test]
+fn unchecked_requests_are_validated_separately() {
+    let requests = vec![
+        JrpcRequest::new_unchecked("some/call", None, Some(1.into())),
+        JrpcRequest::new_unchecked("some/notify", Some(serde_json::json!([1])), None),
+        JrpcRequest::new_unchecked("some/call", None, Some(serde_json::json!({"id": 1}))),
+    ];
+
+    let outcomes: Vec<_> = requests.iter().map(JrpcRequest::validate).collect();
+    assert!(outcomes[0].is_ok());
+    assert!(outcomes[1].is_ok());
+    match &outcomes[2] {
+        Err(ErrorVariant::InvalidJsonRpcId) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match JrpcBatchRequest::new(requests) {
+        Err(ErrorVariant::InvalidJsonRpcId) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_unchecked_requests_are_rejected_by_the_handler() {
+    let request = JrpcRequest::new_unchecked("some/call", None, Some(serde_json::json!([1])));
+    let message = serde_json::to_string(&request).unwrap();
+
+    match JrpcHandler::new().unwrap().handle_message(message) {
+        Err(ErrorVariant::InvalidJsonRpcId) => (),
+        Err(e) => panic!("Unexpected error {:?}", e),
+        Ok(_) => panic!("Invalid request was dispatched"),
+    }
+}
+
+#[test]
+fn lenient_response_defaults_the_version() {
+    let message = r#"{"result": 19, "id": 1}"#;
+
+    match JrpcResponse::parse(message) {
+        Err(ErrorVariant::InvalidRequest(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let response = JrpcResponse::parse_lenient(message).unwrap();
+    assert_eq!(response.get_jsonrpc(), "2.0");
+    assert_eq!(response.get_result(), &Some(JsonValue::from(19)));
+    assert_eq!(response.get_id(), &JsonValue::from(1));
+}
+
+#[test]
+fn lenient_response_is_still_validated() {
+    let both =
+        r#"{"result": 19, "error": {"code": -32603, "message": "a", "data": null}, "id": 1}"#;
+    match JrpcResponse::parse_lenient(both) {
+        Err(ErrorVariant::ResponseCannotContainResultAndError) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match JrpcResponse::parse_lenient(r#"{"jsonrpc": "1.0", "result": 19, "id": 1}"#) {
+        Err(ErrorVariant::InvalidJsonRpcVersion) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn requests_match_responses_by_id() {
+    let response = |id: JsonValue| JrpcResponse::new(Some(JsonValue::Null), None, id).unwrap();
+    let request = |id: Option<JsonValue>| JrpcRequest::new("some/call", None, id).unwrap();
+
+    assert!(request(Some("a".into())).matches_response(&response("a".into())));
+    assert!(request(Some(7.into())).matches_response(&response(7.into())));
+    assert!(request(Some(JsonValue::Null)).matches_response(&response(JsonValue::Null)));
+
+    assert!(!request(Some("7".into())).matches_response(&response(7.into())));
+    assert!(!request(Some(7.into())).matches_response(&response(8.into())));
+    assert!(!request(None).matches_response(&response(JsonValue::Null)));
+}
+
+#[test]
+fn concatenated_messages_are_parsed_one_by_one() {
+    let stream = r#"{"jsonrpc": "2.0", "method": "a", "id": 1}{"jsonrpc": "2.0", "method": "b"}  "#;
+
+    let (first, offset) = JrpcRequest::parse_prefix(stream).unwrap();
+    assert_eq!(first.get_method(), "a");
+    assert_eq!(&stream[offset..offset + 1], "{");
+
+    let rest = &stream[offset..];
+    let (second, offset) = JrpcRequest::parse_prefix(rest).unwrap();
+    assert_eq!(second.get_method(), "b");
+    assert!(second.is_notification());
+    assert_eq!(rest[offset..].trim(), "");
+
+    match JrpcRequest::parse_prefix(&rest[offset..]) {
+        Err(ErrorVariant::JsonParseError(e)) => assert!(e.is_eof()),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn concatenated_responses_are_parsed_one_by_one() {
+    let stream = r#"{"jsonrpc": "2.0", "result": 1, "id": 1}
+{"jsonrpc": "2.0", "result": 2, "id": 2}"#;
+
+    let (first, offset) = JrpcResponse::parse_prefix(stream).unwrap();
+    let (second, _) = JrpcResponse::parse_prefix(&stream[offset..]).unwrap();
+    assert_eq!(first.get_id(), &JsonValue::from(1));
+    assert_eq!(second.get_id(), &JsonValue::from(2));
+}
+
+fn numbered_response(id: i64) -> JrpcResponse {
+    JrpcResponse::new(Some(JsonValue::from(id)), None, JsonValue::from(id)).unwrap()
+}
+
+#[test]
+fn batch_builder_orders_out_of_order_completions() {
+    let mut by_request = BatchResponseBuilder::new(3, BatchOrder::Request);
+    let mut by_completion = BatchResponseBuilder::new(3, BatchOrder::Completion);
+
+    for builder in [&mut by_request, &mut by_completion].iter_mut() {
+        numbered_response(2).merge_into_batch(builder, 2).unwrap();
+        assert!(!builder.is_complete());
+        builder.push(1, None).unwrap();
+        numbered_response(0).merge_into_batch(builder, 0).unwrap();
+        assert!(builder.is_complete());
+    }
+
+    let ids = |responses: Vec<JrpcResponse>| -> Vec<JsonValue> {
+        responses.into_iter().map(|r| r.get_id().clone()).collect()
+    };
+    assert_eq!(
+        ids(by_request.build().unwrap()),
+        vec![JsonValue::from(0), JsonValue::from(2)]
+    );
+    assert_eq!(
+        ids(by_completion.build().unwrap()),
+        vec![JsonValue::from(2), JsonValue::from(0)]
+    );
+}
+
+#[test]
+fn batch_builder_of_notifications_is_empty() {
+    let mut builder = BatchResponseBuilder::new(2, BatchOrder::Request);
+    builder.push(1, None).unwrap().push(0, None).unwrap();
+    assert!(builder.build().unwrap().is_empty());
+}
+
+#[test]
+fn batch_builder_rejects_invalid_slots() {
+    let mut builder = BatchResponseBuilder::new(2, BatchOrder::Request);
+    builder.push(0, None).unwrap();
+
+    match builder.push(0, Some(numbered_response(0))) {
+        Err(ErrorVariant::InvalidBatchSlot(0)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match builder.push(2, None) {
+        Err(ErrorVariant::InvalidBatchSlot(2)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match builder.build() {
+        Err(ErrorVariant::InvalidBatchSlot(1)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[derive(Debug)]
+enum DomainError {
+    BadInput(String),
+    Rpc(ErrorVariant),
+}
+
+impl TryFromJrpcError for DomainError {
+    fn try_from_jrpc_error(error: JrpcError) -> Result<Self, JrpcError> {
+        match error.get_code() {
+            -32602 => Ok(DomainError::BadInput(error.get_message().clone())),
+            _ => Err(error),
+        }
+    }
+}
+
+impl From<ErrorVariant> for DomainError {
+    fn from(error: ErrorVariant) -> Self {
+        DomainError::Rpc(error)
+    }
+}
+
+#[test]
+fn responses_convert_into_typed_results() {
+    let response = JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": [1, 2], "id": 1}"#).unwrap();
+    let result: Result<Vec<u8>, DomainError> = response.into_typed_result();
+    assert_eq!(result.unwrap(), vec![1, 2]);
+
+    let response = JrpcResponse::parse(
+        r#"{"jsonrpc": "2.0", "error": {"code": -32602, "message": "Invalid params"}, "id": 1}"#,
+    )
+    .unwrap();
+    match response.into_typed_result::<Vec<u8>, DomainError>() {
+        Err(DomainError::BadInput(message)) => assert_eq!(message, "Invalid params"),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let response = JrpcResponse::parse(
+        r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 1}"#,
+    )
+    .unwrap();
+    match response.into_typed_result::<Vec<u8>, DomainError>() {
+        Err(DomainError::Rpc(ErrorVariant::ErrorResponse(e))) => assert_eq!(*e.get_code(), -32601),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let response = JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": "a", "id": 1}"#).unwrap();
+    match response.into_typed_result::<Vec<u8>, DomainError>() {
+        Err(DomainError::Rpc(ErrorVariant::JsonParseError(_))) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn response_extensions_are_serialized_at_the_top_level() {
+    let mut response = JrpcResponse::new(Some(JsonValue::from(3)), None, JsonValue::from(1))
+        .unwrap()
+        .with_extension("meta", serde_json::json!({"elapsed_ms": 12}));
+    response
+        .get_extensions_mut()
+        .insert("id".to_string(), JsonValue::from("shadowed"));
+
+    let value = serde_json::to_value(&response).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({"jsonrpc": "2.0", "result": 3, "id": 1, "meta": {"elapsed_ms": 12}})
+    );
+
+    let parsed = JrpcResponse::parse(value.to_string()).unwrap();
+    assert_eq!(
+        parsed.get_extensions().get("meta"),
+        Some(&serde_json::json!({"elapsed_ms": 12}))
+    );
+
+    let plain = JrpcResponse::new(Some(JsonValue::from(3)), None, JsonValue::from(1)).unwrap();
+    assert_eq!(
+        serde_json::to_string(&plain).unwrap(),
+        r#"{"jsonrpc":"2.0","result":3,"id":1}"#
+    );
+}
+
+#[test]
+fn errors_are_logged_by_severity() {
+    let level = |code: i32| JrpcError::new(code, "Failed", None).log_level();
+
+    assert_eq!(level(-32700), log::Level::Warn);
+    assert_eq!(level(-32600), log::Level::Warn);
+    assert_eq!(level(-32601), log::Level::Warn);
+    assert_eq!(level(-32602), log::Level::Warn);
+    assert_eq!(level(-32603), log::Level::Error);
+    assert_eq!(level(-32000), log::Level::Warn);
+    assert_eq!(level(-32099), log::Level::Warn);
+    assert_eq!(level(1), log::Level::Info);
+    assert_eq!(level(-31999), log::Level::Info);
+}
+
+#[test]
+fn request_size_estimate_is_close_to_the_serialized_length() {
+    let requests = vec![
+        JrpcRequest::new("some/method", None, None).unwrap(),
+        JrpcRequest::new("a", Some(serde_json::json!([1, -2.5, null, true])), Some(7.into())).unwrap(),
+        JrpcRequest::new(
+            "some/method",
+            Some(serde_json::json!({"name": "quoted \"value\"", "nested": {"list": [], "flag": false}})),
+            Some(JsonValue::from("abc")),
+        )
+        .unwrap(),
+    ];
+
+    for request in requests {
+        let serialized = serde_json::to_string(&request).unwrap().len();
+        assert_eq!(request.serialized_size().unwrap(), serialized);
+
+        let estimate = request.estimate_size();
+        assert!(estimate <= serialized && serialized - estimate <= 4);
+    }
+}
+
+#[test]
+fn error_category_keeps_existing_data() {
+    let error = JrpcError::new(-32001, "Busy", Some(serde_json::json!({"retry": true})));
+    assert_eq!(
+        error.with_category().get_data(),
+        &Some(serde_json::json!({"retry": true, "category": "ServerError"}))
+    );
+
+    let error = JrpcError::new(7, "Custom", Some(JsonValue::from(1)));
+    assert_eq!(
+        error.with_category().get_data(),
+        &Some(serde_json::json!({"category": "Other", "data": 1}))
+    );
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Range {
+    start: u32,
+    end: u32,
+}
+
+#[test]
+fn params_are_deserialized_into_typed_values() {
+    let request = request_with_params(serde_json::json!({"start": 1, "end": 5}));
+    assert_eq!(
+        request.get_params_as::<Range>().unwrap(),
+        Range { start: 1, end: 5 }
+    );
+
+    let request = request_with_params(serde_json::json!([1, "a"]));
+    assert_eq!(
+        request.get_params_as::<(u32, String)>().unwrap(),
+        (1, "a".to_string())
+    );
+
+    let request = JrpcRequest::new("some/method", None, None).unwrap();
+    assert_eq!(request.get_params_as::<Option<Range>>().unwrap(), None);
+
+    let request = request_with_params(serde_json::json!({"start": "one", "end": 5}));
+    match request.get_params_as::<Range>() {
+        Err(ErrorVariant::InvalidParams(message)) => assert!(message.contains("invalid type")),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn responses_serialize_only_the_present_member() {
+    let response = JrpcResponse::new(Some(JsonValue::from(5)), None, JsonValue::from(1)).unwrap();
+    let value = response.to_json_value();
+    assert_eq!(value["result"], JsonValue::from(5));
+    assert!(value.get("error").is_none());
+    assert_eq!(
+        response.to_json_string().unwrap(),
+        r#"{"jsonrpc":"2.0","result":5,"id":1}"#
+    );
+
+    let error = JrpcError::from(JrpcErrorEnum::MethodNotFound);
+    let response = JrpcResponse::new(None, Some(error), JsonValue::Null).unwrap();
+    let value = response.to_json_value();
+    assert_eq!(value["error"]["code"], JsonValue::from(-32601));
+    assert!(value.get("result").is_none());
+
+    let parsed: JsonValue = serde_json::from_str(&response.to_json_string().unwrap()).unwrap();
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn absent_result_or_error_members_are_not_serialized() {
+    let request = JrpcRequest::new("a", None, Some(JsonValue::from("x"))).unwrap();
+    let success = JrpcResponseParam::generate_result(JsonValue::Null)
+        .and_then(|result| request.generate_response(result))
+        .unwrap();
+    let failure = JrpcResponseParam::generate_error(JrpcError::from(JrpcErrorEnum::InternalError))
+        .and_then(|error| request.generate_response(error))
+        .unwrap();
+
+    let batch = JrpcBatchResponse::new(vec![success, failure]);
+    let serialized = serde_json::to_string(&batch).unwrap();
+    let parsed: JsonValue = serde_json::from_str(&serialized).unwrap();
+
+    // A `null` result is still a result, and must be emitted
+    let success = parsed[0].as_object().unwrap();
+    assert_eq!(success.get("result"), Some(&JsonValue::Null));
+    assert!(!success.contains_key("error"));
+
+    let failure = parsed[1].as_object().unwrap();
+    assert!(failure.contains_key("error"));
+    assert!(!failure.contains_key("result"));
+}
+
+#[test]
+fn builder_produces_calls_and_notifications() {
+    let call = JrpcRequest::builder("a")
+        .method("some/method")
+        .params(vec![1, 2])
+        .id(7)
+        .build()
+        .unwrap();
+    assert_eq!(call.get_method(), "some/method");
+    assert_eq!(call.get_params(), &Some(serde_json::json!([1, 2])));
+    assert_eq!(call.get_id(), &Some(JsonValue::from(7)));
+    assert!(!call.is_notification());
+
+    let notification = JrpcRequestBuilder::new("some/method")
+        .id("x")
+        .notification()
+        .build()
+        .unwrap();
+    assert!(notification.is_notification());
+    assert!(notification.get_params().is_none());
+    let serialized: JsonValue = serde_json::to_value(&notification).unwrap();
+    assert!(serialized.get("id").is_none());
+
+    match JrpcRequest::builder("a").id(vec![1]).build() {
+        Err(ErrorVariant::InvalidJsonRpcId) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn error_data_survives_serialization() {
+    let data = serde_json::json!({"field": "name", "reason": "too long"});
+
+    let error = JrpcError::with_data(100, "Rejected", data.clone());
+    let parsed = JrpcError::parse(serde_json::to_string(&error).unwrap()).unwrap();
+    assert_eq!(parsed.get_code(), &100);
+    assert_eq!(parsed.get_data(), &Some(data.clone()));
+
+    let error = JrpcErrorEnum::InvalidParams.into_error_with_data(data.clone());
+    let parsed = JrpcError::parse(serde_json::to_string(&error).unwrap()).unwrap();
+    assert_eq!(parsed.get_code(), &-32602);
+    assert_eq!(
+        parsed.get_message(),
+        JrpcError::from(JrpcErrorEnum::InvalidParams).get_message()
+    );
+    assert_eq!(parsed.get_data(), &Some(data));
+
+    let mut error = JrpcError::from(JrpcErrorEnum::InternalError);
+    error.set_data(JsonValue::from("details"));
+    let parsed = JrpcError::parse(serde_json::to_string(&error).unwrap()).unwrap();
+    assert_eq!(parsed.get_data(), &Some(JsonValue::from("details")));
+}
+
+#[test]
+fn checked_errors_reject_unknown_reserved_codes() {
+    match JrpcError::new_checked(-32500, "Oops", None) {
+        Err(ErrorVariant::InvalidErrorCode(-32500)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+    match JrpcError::new_checked(-32768, "Oops", None) {
+        Err(ErrorVariant::InvalidErrorCode(-32768)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    let error = JrpcError::new_checked(100, "Application error", None).unwrap();
+    assert_eq!(error.get_code(), &100);
+
+    // Predefined codes, and server errors defined by the implementation, are accepted
+    assert!(JrpcError::new_checked(-32602, "Invalid params", None).is_ok());
+    assert!(JrpcError::new_checked(-32001, "Busy", None).is_ok());
+}
+
+#[test]
+fn parser_types_compare_as_whole_values() {
+    let response = |result: i32| {
+        JrpcResponse::new(Some(JsonValue::from(result)), None, JsonValue::from(1)).unwrap()
+    };
+    assert_eq!(response(1), response(1));
+    assert_ne!(response(1), response(2));
+
+    let parsed = JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": 1, "id": 1}"#).unwrap();
+    assert_eq!(parsed, response(1));
+
+    let request = JrpcRequest::new("a", Some(JsonValue::from(1)), Some(JsonValue::from(1)));
+    assert_eq!(
+        request.unwrap(),
+        JrpcRequest::parse(r#"{"jsonrpc": "2.0", "method": "a", "params": 1, "id": 1}"#).unwrap()
+    );
+
+    // The raw id and the matched signature are not members of the message
+    let message = r#"{"jsonrpc": "2.0", "method": "a", "id": 12345678901234567890123}"#;
+    let preserved = JrpcRequest::parse_preserving_id(message).unwrap();
+    assert_eq!(preserved, JrpcRequest::parse(message).unwrap());
+
+    let result = JrpcResponseParam::JrpcResult(JsonValue::from(1));
+    let response = JrpcResponse::from_jrpc_request(&preserved, result.clone()).unwrap();
+    let request = JrpcRequest::parse(message).unwrap();
+    assert_eq!(
+        response,
+        JrpcResponse::from_jrpc_request(&request, result).unwrap()
+    );
+
+    let error = JrpcError::from(JrpcErrorEnum::InvalidParams);
+    assert_eq!(error, JrpcError::new(-32602, error.get_message(), None));
+    assert_ne!(error, JrpcError::from(JrpcErrorEnum::InternalError));
+    assert_ne!(
+        JrpcResponseParam::JrpcError(error),
+        JrpcResponseParam::JrpcResult(JsonValue::Null)
+    );
+}
+
+#[test]
+fn json_errors_convert_into_parse_errors() {
+    fn parse(message: &str) -> Result<JsonValue, ErrorVariant> {
+        Ok(serde_json::from_str(message)?)
+    }
+
+    assert_eq!(parse("[1]").unwrap(), serde_json::json!([1]));
+    match parse("[1,") {
+        Err(ErrorVariant::JsonParseError(e)) => assert!(e.is_eof()),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    let error = std::io::Error::other("closed");
+    match ErrorVariant::from(error) {
+        ErrorVariant::IoError(e) => assert_eq!(e.to_string(), "closed"),
+        e => panic!("Unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn malformed_requests_are_told_apart_from_broken_json() {
+    match JrpcRequest::parse(r#"{"jsonrpc": "2.0", "params": [1], "id": 1}"#) {
+        Err(ErrorVariant::InvalidRequest(message)) => assert!(message.contains("method")),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    match JrpcRequest::parse(r#"{"jsonrpc": "1.0", "method": "a", "id": 1}"#) {
+        Err(ErrorVariant::InvalidJsonRpcVersion) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    match JrpcRequest::parse(r#"{"jsonrpc": "2.0", "method": "a", "id": 1"#) {
+        Err(ErrorVariant::JsonParseError(_)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    let error = JrpcError::from(ErrorVariant::InvalidRequest("missing field".to_string()));
+    assert_eq!(error.get_code(), &-32600);
+}
+
+#[test]
+fn every_entry_point_tells_malformed_messages_apart() {
+    let request = r#"{"jsonrpc": "2.0", "params": [1], "id": 1}"#;
+    let response = r#"{"jsonrpc": "2.0", "result": 2}"#;
+
+    let requests = [
+        JrpcRequest::parse_prefix(request).map(|_| ()),
+        JrpcRequest::parse_preserving_id(request).map(|_| ()),
+        JrpcRequest::parse_lenient(request).map(|_| ()),
+    ];
+    let responses = [
+        JrpcResponse::parse(response).map(|_| ()),
+        JrpcResponse::parse_prefix(response).map(|_| ()),
+        JrpcResponse::parse_lenient(response).map(|_| ()),
+    ];
+    for result in requests.iter().chain(responses.iter()) {
+        match result {
+            Err(ErrorVariant::InvalidRequest(_)) => (),
+            r => panic!("Unexpected result {:?}", r),
+        }
+    }
+
+    match JrpcRequest::parse_preserving_id(r#"{"jsonrpc": "2.0", "method": "a", "id": 1"#) {
+        Err(ErrorVariant::JsonParseError(_)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn messages_display_as_compact_json() {
+    let request = JrpcRequest::new(
+        "a",
+        Some(serde_json::json!([1, "b"])),
+        Some(JsonValue::from(1)),
+    )
+    .unwrap();
+    let displayed = request.to_string();
+    assert!(!displayed.contains(' '));
+    assert_eq!(JrpcRequest::parse(displayed).unwrap(), request);
+
+    let error = JrpcError::from(JrpcErrorEnum::InvalidParams);
+    let response = JrpcResponse::new(None, Some(error), JsonValue::from("c")).unwrap();
+    assert_eq!(
+        response.to_string(),
+        r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid method parameter(s).","data":null},"id":"c"}"#
+    );
+    assert_eq!(JrpcResponse::parse(response.to_string()).unwrap(), response);
+}
+
+#[test]
+fn messages_are_told_apart_as_requests_or_responses() {
+    match JrpcMessage::parse(r#"{"jsonrpc": "2.0", "method": "a", "id": 1}"#).unwrap() {
+        JrpcMessage::Request(request) => assert_eq!(request.get_method(), "a"),
+        m => panic!("Unexpected message {:?}", m),
+    }
+
+    match JrpcMessage::parse(r#"{"jsonrpc": "2.0", "result": 2, "id": 1}"#).unwrap() {
+        JrpcMessage::Response(response) => {
+            assert_eq!(response.get_result(), &Some(JsonValue::from(2)))
+        }
+        m => panic!("Unexpected message {:?}", m),
+    }
+
+    match JrpcMessage::parse(r#"{"jsonrpc":"2.0","result":null,"id":1}"#).unwrap() {
+        JrpcMessage::Response(response) => {
+            assert_eq!(response.get_result(), &Some(JsonValue::Null))
+        }
+        m => panic!("Unexpected message {:?}", m),
+    }
+
+    match JrpcMessage::parse(r#"{"jsonrpc": "2.0", "method": "a", "result": 2, "id": 1}"#) {
+        Err(ErrorVariant::InvalidRequest(_)) => (),
+        m => panic!("Unexpected message {:?}", m),
+    }
+
+    let batch = JrpcMessage::parse_batch(
+        r#"[
+            {"jsonrpc": "2.0", "method": "a"},
+            {"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 2}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(batch.len(), 2);
+    assert!(matches!(&batch[0], JrpcMessage::Request(r) if r.is_notification()));
+    assert!(matches!(&batch[1], JrpcMessage::Response(r) if r.get_error().is_some()));
+
+    assert!(matches!(
+        JrpcMessage::parse_batch("[]"),
+        Err(ErrorVariant::EmptyBatch)
+    ));
+}
+
+#[test]
+fn responses_need_exactly_one_of_result_and_error() {
+    let error = || Some(JrpcError::from(JrpcErrorEnum::InternalError));
+
+    match JrpcResponse::new(Some(JsonValue::from(1)), error(), JsonValue::from(1)) {
+        Err(ErrorVariant::ResponseCannotContainResultAndError) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    match JrpcResponse::new(None, None, JsonValue::from(1)) {
+        Err(ErrorVariant::ResponseMustContainResultOrError) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let neither = r#"{"jsonrpc": "2.0", "id": 1}"#;
+    match JrpcResponse::parse(neither) {
+        Err(ErrorVariant::ResponseMustContainResultOrError) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match JrpcResponse::parse_lazy(neither) {
+        Err(ErrorVariant::ResponseMustContainResultOrError) => (),
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let both = r#"{"jsonrpc": "2.0", "result": 1, "error": {"code": 1, "message": "a"}, "id": 1}"#;
+    match JrpcResponse::parse_lazy(both) {
+        Err(ErrorVariant::ResponseCannotContainResultAndError) => (),
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let error = JrpcError::from(ErrorVariant::ResponseMustContainResultOrError);
+    assert_eq!(error.get_code(), &-32600);
+}
+
+#[test]
+fn error_variants_convert_into_error_responses() {
+    let response = JrpcResponse::from_error_variant(
+        ErrorVariant::MethodSignatureNotFound("a".to_string()),
+        JsonValue::from(7),
+    );
+    assert_eq!(response.get_id(), &JsonValue::from(7));
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32601);
+    assert!(response.get_result().is_none());
+
+    let response = JrpcResponse::from_error_variant(
+        ErrorVariant::InvalidParams("b".to_string()),
+        JsonValue::from("c"),
+    );
+    assert_eq!(response.get_id(), &JsonValue::from("c"));
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32602);
+
+    let response =
+        JrpcResponse::from_error_variant(ErrorVariant::InternalError, serde_json::json!([1]));
+    assert_eq!(response.get_id(), &JsonValue::Null);
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32603);
+}
+
+#[test]
+fn lenient_requests_accept_legacy_versions() {
+    for message in &[
+        r#"{"method": "a", "params": [1], "id": 1}"#,
+        r#"{"jsonrpc": "2", "method": "a", "params": [1], "id": 1}"#,
+    ] {
+        let request = JrpcRequest::parse_lenient(message).unwrap();
+        assert_eq!(request.get_jsonrpc(), "2.0");
+        assert_eq!(request.get_method(), "a");
+
+        match JrpcRequest::parse(message) {
+            Err(ErrorVariant::InvalidJsonRpcVersion) | Err(ErrorVariant::InvalidRequest(_)) => (),
+            r => panic!("Unexpected outcome {:?}", r),
+        }
+    }
+
+    match JrpcRequest::parse_lenient(r#"{"jsonrpc": "1.0", "method": "a", "id": 1}"#) {
+        Err(ErrorVariant::InvalidJsonRpcVersion) => (),
+        r => panic!("Unexpected outcome {:?}", r),
+    }
+}
+
+#[test]
+fn responses_are_rewritten_in_place() {
+    let data = serde_json::json!({"password": "secret", "user": "a"});
+    let error = JrpcError::with_data(-32001, "Unauthorized", data);
+    let mut response = JrpcResponse::new(None, Some(error), JsonValue::from(1)).unwrap();
+
+    if let Some(error) = response.get_error_mut() {
+        if let Some(JsonValue::Object(data)) = error.get_data_mut() {
+            data.remove("password");
+        }
+    }
+    assert_eq!(
+        response.get_error().as_ref().unwrap().get_data(),
+        &Some(serde_json::json!({"user": "a"}))
+    );
+
+    let response = JrpcResponse::new(Some(JsonValue::from(1)), None, JsonValue::from(1));
+    let mut response = response.unwrap();
+    *response.get_result_mut() = Some(JsonValue::from(2));
+    assert_eq!(response.get_result(), &Some(JsonValue::from(2)));
+}
+
+#[test]
+fn ids_are_read_as_integers_or_strings() {
+    let request = JrpcRequest::new("some/method", None, Some(JsonValue::from(7))).unwrap();
+    assert_eq!(request.id_as_i64(), Some(7));
+    assert_eq!(request.id_as_str(), None);
+
+    let request = JrpcRequest::new("some/method", None, Some(JsonValue::from("a"))).unwrap();
+    assert_eq!(request.id_as_i64(), None);
+    assert_eq!(request.id_as_str(), Some("a"));
+
+    let notification = JrpcRequest::new("some/method", None, None).unwrap();
+    assert_eq!(notification.id_as_i64(), None);
+    assert_eq!(notification.id_as_str(), None);
+
+    let response = JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": 1, "id": -3}"#).unwrap();
+    assert_eq!(response.id_as_i64(), Some(-3));
+    assert_eq!(response.id_as_str(), None);
+
+    let response = JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": 1, "id": "b"}"#).unwrap();
+    assert_eq!(response.id_as_i64(), None);
+    assert_eq!(response.id_as_str(), Some("b"));
+
+    let response = JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": 1, "id": 1.5}"#).unwrap();
+    assert_eq!(response.id_as_i64(), None);
+}
+
+#[test]
+fn responses_serialize_compact_or_pretty() {
+    let response =
+        JrpcResponse::parse(r#"{"jsonrpc": "2.0", "result": {"a": [1, 2]}, "id": "x"}"#).unwrap();
+
+    let compact = response.to_json_string().unwrap();
+    let pretty = response.to_json_string_pretty().unwrap();
+    assert!(!compact.contains('\n'));
+    assert!(pretty.contains('\n'));
+
+    assert_eq!(JrpcResponse::parse(pretty).unwrap(), response);
+    assert_eq!(JrpcResponse::parse(compact).unwrap(), response);
+}
+
+#[test]
+fn null_results_are_successful_responses() {
+    let message = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+    let response = JrpcResponse::parse(message).unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::Null));
+    assert!(response.get_error().is_none());
+    assert_eq!(response.to_string(), message);
+    assert_eq!(JrpcResponse::parse(response.to_string()).unwrap(), response);
+
+    let lenient = JrpcResponse::parse_lenient(r#"{"result": null, "id": 1}"#).unwrap();
+    assert_eq!(lenient, response);
+
+    let lazy = JrpcResponse::parse_lazy(message).unwrap();
+    assert_eq!(lazy.get_raw_result().map(|raw| raw.get()), Some("null"));
+    assert_eq!(lazy.into_response().unwrap(), response);
+
+    let both =
+        r#"{"jsonrpc": "2.0", "result": null, "error": {"code": 1, "message": "a"}, "id": 1}"#;
+    match JrpcResponse::parse(both) {
+        Err(ErrorVariant::ResponseCannotContainResultAndError) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
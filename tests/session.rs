@@ -0,0 +1,73 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+
+generate_method!(
+    NeverReady,
+    impl Future for NeverReady {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            Ok(Async::NotReady)
+        }
+    }
+);
+
+const MESSAGE: &str = r#"{"jsonrpc": "2.0", "method": "some/pending", "id": 1}"#;
+
+fn session() -> JrpcSession<'static> {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/pending", NeverReady::new().unwrap())
+        .unwrap();
+    JrpcSession::new(handler).unwrap()
+}
+
+#[test]
+fn in_flight_ids_can_be_reused_by_default() {
+    let session = session();
+
+    let _first = session.handle_message(MESSAGE).unwrap();
+    assert!(session.handle_message(MESSAGE).is_ok());
+}
+
+#[test]
+fn in_flight_ids_are_unique_when_enforced() {
+    let session = session();
+    session.set_enforce_unique_ids(true).unwrap();
+
+    let first = session.handle_message(MESSAGE).unwrap();
+    match session.handle_message(MESSAGE) {
+        Err(e @ ErrorVariant::DuplicateRequestId(_)) => {
+            assert_eq!(JrpcError::from(e).get_code(), &-32600)
+        }
+        _ => panic!("The duplicated id was dispatched"),
+    }
+
+    // Other ids and notifications are not affected
+    assert!(session
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/pending", "id": 2}"#)
+        .is_ok());
+    assert!(session
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "some/pending"}"#)
+        .is_ok());
+
+    drop(first);
+    assert!(session.handle_message(MESSAGE).is_ok());
+}
+
+#[test]
+fn sessions_start_with_the_setting_of_the_handler() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/pending", NeverReady::new().unwrap())
+        .and_then(|h| h.set_enforce_unique_ids(true))
+        .unwrap();
+    let session = JrpcSession::new(handler).unwrap();
+
+    let _first = session.handle_message(MESSAGE).unwrap();
+    match session.handle_message(MESSAGE) {
+        Err(ErrorVariant::DuplicateRequestId(_)) => (),
+        _ => panic!("The duplicated id was dispatched"),
+    }
+}
@@ -0,0 +1,174 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::*;
+use std::io::{self, Cursor, Read};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// Sum the streamed params, reporting each element as it is received
+struct Sum(Mutex<mpsc::Sender<JsonValue>>);
+
+impl<'a> JrpcStreamingMethodTrait<'a> for Sum {
+    fn generate_future(
+        &self,
+        params: ParamsStream,
+    ) -> Result<Box<dyn 'a + Future<Item = JsonValue, Error = ErrorVariant>>, ErrorVariant> {
+        let seen = self.0.lock().unwrap().clone();
+
+        Ok(Box::new(
+            params
+                .fold(0, move |sum, element| {
+                    let _ = seen.send(element.clone());
+                    element
+                        .as_i64()
+                        .map(|n| sum + n)
+                        .ok_or_else(|| ErrorVariant::InvalidParams("expected integers".to_string()))
+                })
+                .map(JsonValue::from),
+        ))
+    }
+}
+
+/// Reader yielding the chunks sent to it, blocking until the next one arrives
+struct Chunks(mpsc::Receiver<Vec<u8>>, Cursor<Vec<u8>>);
+
+impl Read for Chunks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.1.read(buf)?;
+            if read > 0 {
+                return Ok(read);
+            }
+
+            match self.0.recv() {
+                Ok(chunk) => self.1 = Cursor::new(chunk),
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+fn handler() -> (JrpcHandler<'static>, mpsc::Receiver<JsonValue>) {
+    let (sender, receiver) = mpsc::channel();
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_streaming_method("sum", Sum(Mutex::new(sender)))
+        .unwrap();
+    (handler, receiver)
+}
+
+fn handle(handler: &JrpcHandler, message: &str) -> Result<Option<JrpcResponse>, ErrorVariant> {
+    handler
+        .handle_reader(Cursor::new(message.as_bytes().to_vec()))
+        .and_then(|future| future.wait())
+}
+
+#[test]
+fn params_are_streamed_before_the_message_is_read() {
+    let (handler, seen) = handler();
+    let (chunks, receiver) = mpsc::channel();
+
+    let writer = thread::spawn(move || {
+        chunks
+            .send(br#"{"jsonrpc": "2.0", "method": "sum", "params": [1, 2, "#.to_vec())
+            .unwrap();
+
+        // The first element reaches the method while the rest of the message is still unsent
+        let first = seen.recv_timeout(Duration::from_secs(5));
+        chunks.send(br#"3], "id": 7}"#.to_vec()).unwrap();
+        first
+    });
+
+    let response = handler
+        .handle_reader(Chunks(receiver, Cursor::new(vec![])))
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(writer.join().unwrap(), Ok(JsonValue::from(1)));
+    assert_eq!(response.get_result(), &Some(JsonValue::from(6)));
+    assert_eq!(response.get_id(), &JsonValue::from(7));
+}
+
+#[test]
+fn streamed_notifications_resolve_to_none() {
+    let (handler, _seen) = handler();
+
+    let response = handle(
+        &handler,
+        r#"{"jsonrpc": "2.0", "method": "sum", "params": [1]}"#,
+    );
+    assert!(response.unwrap().is_none());
+}
+
+#[test]
+fn invalid_streamed_requests_fail_once_read() {
+    let (handler, _seen) = handler();
+
+    match handle(
+        &handler,
+        r#"{"jsonrpc": "2.0", "method": "sum", "params": {"a": 1}, "id": 1}"#,
+    ) {
+        Err(ErrorVariant::InvalidParams(_)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    match handle(
+        &handler,
+        r#"{"jsonrpc": "1.0", "method": "sum", "params": [1], "id": 1}"#,
+    ) {
+        Err(ErrorVariant::InvalidJsonRpcVersion) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    match handle(
+        &handler,
+        r#"{"jsonrpc": "2.0", "method": "sum", "params": [1, }"#,
+    ) {
+        Err(_) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn other_messages_are_dispatched_in_full() {
+    let (handler, _seen) = handler();
+
+    // Streaming methods require `method` before `params`
+    match handle(
+        &handler,
+        r#"{"params": [1], "jsonrpc": "2.0", "method": "sum", "id": 1}"#,
+    ) {
+        Err(ErrorVariant::MethodSignatureNotFound(method)) => assert_eq!(method, "sum"),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    assert!(handler.unregister_method("sum").unwrap());
+    match handle(
+        &handler,
+        r#"{"jsonrpc": "2.0", "method": "sum", "params": [1], "id": 1}"#,
+    ) {
+        Err(ErrorVariant::MethodSignatureNotFound(_)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn streamed_requests_are_refused_past_pre_hooks() {
+    let (handler, seen) = handler();
+    handler
+        .add_pre_hook(|_| Err(JrpcError::new(-32001, "Unauthorized", None)))
+        .unwrap();
+
+    match handle(
+        &handler,
+        r#"{"jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 1}"#,
+    ) {
+        Err(ErrorVariant::InvalidRequest(_)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    // The method never saw its params
+    assert!(seen.try_recv().is_err());
+}
@@ -0,0 +1,53 @@
+use futures_jsonrpc::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Point {
+    x: i64,
+    #[serde(default)]
+    y: i64,
+}
+
+fn request(params: JsonValue) -> JrpcRequest {
+    JrpcRequest::new("some/method", Some(params), Some(JsonValue::from(1))).unwrap()
+}
+
+#[test]
+fn expected_params_are_accepted_by_both_extractions() {
+    let request = request(serde_json::json!({"x": 1, "y": 2}));
+
+    assert_eq!(
+        request.get_params_as::<Point>().unwrap(),
+        Point { x: 1, y: 2 }
+    );
+    assert_eq!(
+        request.get_params_as_strict::<Point>().unwrap(),
+        Point { x: 1, y: 2 }
+    );
+}
+
+#[test]
+fn extra_fields_are_ignored_by_lenient_extraction() {
+    // A misspelled `y` silently falls back to its default
+    let request = request(serde_json::json!({"x": 1, "Y": 2}));
+    assert_eq!(
+        request.get_params_as::<Point>().unwrap(),
+        Point { x: 1, y: 0 }
+    );
+}
+
+#[test]
+fn extra_fields_are_rejected_by_strict_extraction() {
+    let request = request(serde_json::json!({"x": 1, "Y": 2}));
+
+    match request.get_params_as_strict::<Point>() {
+        Err(ErrorVariant::InvalidParams(message)) => assert!(message.contains("`Y`")),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let request = self::request(serde_json::json!({"y": 2}));
+    match request.get_params_as_strict::<Point>() {
+        Err(ErrorVariant::InvalidParams(message)) => assert!(message.contains("`x`")),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
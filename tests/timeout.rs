@@ -0,0 +1,213 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::futures::sync::oneshot;
+use futures_jsonrpc::*;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Reply with the params after sleeping for a while, on another thread
+struct Sleeps(Duration);
+
+impl<'a> JrpcMethodTrait<'a> for Sleeps {
+    fn generate_future(
+        &self,
+        request: JrpcRequest,
+    ) -> Result<Box<dyn 'a + Future<Item = Option<JrpcResponse>, Error = ErrorVariant>>, ErrorVariant>
+    {
+        let (sender, receiver) = oneshot::channel();
+        let duration = self.0;
+
+        thread::spawn(move || {
+            thread::sleep(duration);
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+            let response = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result));
+            let _ = sender.send(response);
+        });
+
+        Ok(Box::new(
+            receiver
+                .map_err(|_| ErrorVariant::InternalError)
+                .and_then(|response| response.map(Some)),
+        ))
+    }
+}
+
+fn handler() -> JrpcHandler<'static> {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("fast", Sleeps(Duration::from_millis(0)))
+        .unwrap()
+        .register_method("slow", Sleeps(Duration::from_millis(200)))
+        .unwrap();
+    handler
+}
+
+fn call(handler: &JrpcHandler, method: &str) -> JrpcResponse {
+    let message = format!(
+        r#"{{"jsonrpc": "2.0", "method": "{}", "params": 1, "id": "a"}}"#,
+        method
+    );
+
+    handler
+        .handle_message(message)
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn methods_resolve_within_their_own_timeouts() {
+    let handler = handler();
+    handler
+        .set_default_timeout(Some(Duration::from_millis(50)))
+        .unwrap()
+        .set_method_timeout("fast", Duration::from_millis(100))
+        .unwrap()
+        .set_method_timeout("slow", Duration::from_secs(10))
+        .unwrap();
+
+    let response = call(&handler, "fast");
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+
+    let response = call(&handler, "slow");
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+}
+
+#[test]
+fn methods_exceeding_their_timeout_are_answered_with_an_error() {
+    let handler = handler();
+    handler
+        .set_method_timeout("slow", Duration::from_millis(20))
+        .unwrap();
+
+    let started = Instant::now();
+    let response = call(&handler, "slow");
+    assert!(started.elapsed() < Duration::from_millis(200));
+
+    let error = response.get_error().clone().unwrap();
+    assert_eq!(*error.get_code(), -32000);
+    assert_eq!(
+        error.get_data(),
+        &Some(serde_json::json!({"timeout_ms": 20}))
+    );
+    assert_eq!(response.get_id(), &JsonValue::from("a"));
+    assert_eq!(handler.inflight_count().unwrap(), 0);
+
+    let response = call(&handler, "fast");
+    assert!(response.get_error().is_none());
+}
+
+#[test]
+fn methods_fall_back_to_the_default_timeout() {
+    let handler = handler();
+    handler
+        .set_default_timeout(Some(Duration::from_millis(20)))
+        .unwrap()
+        .set_method_timeout("slow", Duration::from_secs(10))
+        .unwrap();
+    assert!(call(&handler, "slow").get_error().is_none());
+
+    assert!(handler.clear_method_timeout("slow").unwrap());
+    assert!(!handler.clear_method_timeout("slow").unwrap());
+    assert_eq!(
+        *call(&handler, "slow")
+            .get_error()
+            .clone()
+            .unwrap()
+            .get_code(),
+        -32000
+    );
+
+    handler.set_default_timeout(None).unwrap();
+    assert!(call(&handler, "slow").get_error().is_none());
+}
+
+#[test]
+fn passed_deadlines_are_answered_right_away() {
+    let handler = handler();
+    let deadline = Instant::now() - Duration::from_millis(1);
+
+    let response = handler
+        .handle_message_with_deadline(
+            r#"{"jsonrpc": "2.0", "method": "slow", "params": 1, "id": 7}"#,
+            deadline,
+        )
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(*response.get_error().clone().unwrap().get_code(), -32000);
+    assert_eq!(response.get_id(), &JsonValue::from(7));
+    assert_eq!(handler.inflight_count().unwrap(), 0);
+}
+
+#[test]
+fn deadlines_bound_the_dispatch() {
+    let handler = handler();
+
+    let started = Instant::now();
+    let response = handler
+        .handle_message_with_deadline(
+            r#"{"jsonrpc": "2.0", "method": "slow", "params": 1, "id": 7}"#,
+            started + Duration::from_millis(20),
+        )
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert!(started.elapsed() < Duration::from_millis(200));
+    assert_eq!(*response.get_error().clone().unwrap().get_code(), -32000);
+
+    let response = handler
+        .handle_message_with_deadline(
+            r#"{"jsonrpc": "2.0", "method": "fast", "params": 1, "id": 8}"#,
+            Instant::now() + Duration::from_secs(10),
+        )
+        .and_then(|future| future.wait())
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+}
+
+#[test]
+fn methods_can_be_registered_with_a_timeout() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method_with_timeout(
+            "slow",
+            Sleeps(Duration::from_millis(200)),
+            Duration::from_millis(20),
+        )
+        .unwrap();
+
+    let started = Instant::now();
+    let response = call(&handler, "slow");
+    assert!(started.elapsed() < Duration::from_millis(200));
+    assert_eq!(*response.get_error().clone().unwrap().get_code(), -32000);
+    assert_eq!(response.get_id(), &JsonValue::from("a"));
+
+    let response = handler
+        .handle_message(r#"{"jsonrpc": "2.0", "method": "slow", "params": 1}"#)
+        .and_then(|future| future.wait())
+        .unwrap();
+    assert!(response.is_none());
+}
+
+#[test]
+fn long_timeouts_leave_no_trace_after_fast_methods() {
+    let handler = handler();
+    handler
+        .set_method_timeout("fast", Duration::from_secs(3600))
+        .unwrap();
+
+    // Each deadline is dropped with its method, instead of waiting an hour in the timer
+    for _ in 0..1000 {
+        let response = call(&handler, "fast");
+        assert_eq!(response.get_result(), &Some(JsonValue::from(1)));
+    }
+
+    handler
+        .set_method_timeout("slow", Duration::from_millis(20))
+        .unwrap();
+    let response = call(&handler, "slow");
+    assert_eq!(*response.get_error().clone().unwrap().get_code(), -32000);
+}
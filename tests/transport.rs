@@ -0,0 +1,221 @@
+use futures_jsonrpc::futures::prelude::*;
+use futures_jsonrpc::futures::sync::mpsc::unbounded;
+use futures_jsonrpc::transport::{serve_duplex, serve_tcp, LineDelimitedCodec};
+use futures_jsonrpc::*;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tokio::codec::{Decoder, Encoder};
+use tokio::net::TcpListener;
+use tokio::runtime::current_thread::Runtime;
+
+generate_method!(
+    CopyParams,
+    impl Future for CopyParams {
+        type Item = Option<JrpcResponse>;
+        type Error = ErrorVariant;
+
+        fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
+            let request = self.get_request()?;
+            let params = request.get_params().clone().unwrap_or(JsonValue::Null);
+
+            let message = JrpcResponseParam::generate_result(params)
+                .and_then(|result| request.generate_response(result))?;
+
+            Ok(Async::Ready(Some(message)))
+        }
+    }
+);
+
+#[test]
+fn message_split_across_reads_is_reassembled() {
+    let mut codec = LineDelimitedCodec::new();
+    let mut buf = bytes::BytesMut::new();
+
+    buf.extend_from_slice(br#"{"jsonrpc": "2.0", "met"#);
+    assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+    buf.extend_from_slice(b"hod\": \"a\"}\r\n");
+    assert_eq!(
+        codec.decode(&mut buf).unwrap().unwrap(),
+        r#"{"jsonrpc": "2.0", "method": "a"}"#
+    );
+    assert!(buf.is_empty());
+    assert_eq!(codec.decode(&mut buf).unwrap(), None);
+}
+
+#[test]
+fn messages_coalesced_in_one_read_are_split() {
+    let mut codec = LineDelimitedCodec::new();
+    let mut buf = bytes::BytesMut::from(&b"{\"a\": 1}\n\n{\"b\": 2}\n{\"c\""[..]);
+
+    assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), r#"{"a": 1}"#);
+    assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), r#"{"b": 2}"#);
+    assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+    buf.extend_from_slice(b": 3}");
+    assert_eq!(codec.decode_eof(&mut buf).unwrap().unwrap(), r#"{"c": 3}"#);
+    assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+}
+
+#[test]
+fn messages_are_encoded_one_per_line() {
+    let mut codec = LineDelimitedCodec::new();
+    let mut buf = bytes::BytesMut::new();
+
+    codec.encode(r#"{"a": 1}"#.to_string(), &mut buf).unwrap();
+    codec.encode(r#"{"b": 2}"#.to_string(), &mut buf).unwrap();
+    assert_eq!(&buf[..], &b"{\"a\": 1}\n{\"b\": 2}\n"[..]);
+}
+
+#[test]
+fn tcp_server_replies_line_by_line() {
+    let (addr_tx, addr_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let handler = JrpcHandler::new().unwrap();
+        handler
+            .register_method("some/copyParams", CopyParams::new().unwrap())
+            .unwrap();
+
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        addr_tx.send(listener.local_addr().unwrap()).unwrap();
+
+        let mut runtime = Runtime::new().unwrap();
+        runtime.block_on(serve_tcp(listener, handler)).unwrap();
+    });
+
+    let addr = addr_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    let mut client = TcpStream::connect(addr).unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let mut lines = BufReader::new(client.try_clone().unwrap()).lines();
+
+    client
+        .write_all(br#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": [1], "id": 1}"#)
+        .unwrap();
+    client.flush().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    client
+        .write_all(
+            b"\n{\"jsonrpc\": \"2.0\", \"method\": \"some/copyParams\", \"params\": [2]}\n[]\n",
+        )
+        .unwrap();
+
+    let response = JrpcResponse::parse(lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(response.get_result(), &Some(serde_json::json!([1])));
+    assert_eq!(response.get_id(), &JsonValue::from(1));
+
+    // The notification has no reply, so the next one belongs to the empty batch
+    let response = JrpcResponse::parse(lines.next().unwrap().unwrap()).unwrap();
+    assert_eq!(response.get_error().as_ref().unwrap().get_code(), &-32600);
+}
+
+#[test]
+fn duplex_channels_exchange_requests_responses_and_notifications() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .unwrap();
+
+    let (incoming_tx, incoming_rx) = unbounded::<String>();
+    let (outgoing_tx, outgoing_rx) = unbounded::<String>();
+    let (notification_tx, notification_rx) = unbounded::<JrpcRequest>();
+
+    incoming_tx
+        .unbounded_send(
+            r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": [1], "id": 1}"#
+                .to_string(),
+        )
+        .unwrap();
+    incoming_tx
+        .unbounded_send(
+            r#"[{"jsonrpc": "2.0", "method": "some/copyParams", "params": [2], "id": 2}]"#
+                .to_string(),
+        )
+        .unwrap();
+    drop(incoming_tx);
+    notification_tx
+        .unbounded_send(JrpcRequest::prepare_to_send_notification("some/event", None).unwrap())
+        .unwrap();
+
+    serve_duplex(
+        handler,
+        incoming_rx.map_err(|_| ErrorVariant::InternalError),
+        outgoing_tx.sink_map_err(|_| ErrorVariant::InternalError),
+        notification_rx.map_err(|_| ErrorVariant::InternalError),
+    )
+    .wait()
+    .unwrap();
+
+    // The server stopped with the incoming stream, even though notifications are still open
+    assert!(notification_tx
+        .unbounded_send(JrpcRequest::prepare_to_send_notification("some/late", None).unwrap())
+        .is_err());
+
+    let outgoing: Vec<JsonValue> = outgoing_rx
+        .wait()
+        .map(|message| serde_json::from_str(&message.unwrap()).unwrap())
+        .collect();
+    assert_eq!(outgoing.len(), 3);
+
+    let replies: Vec<&JsonValue> = outgoing
+        .iter()
+        .filter(|m| m.get("method").is_none())
+        .collect();
+    assert_eq!(replies[0]["result"], serde_json::json!([1]));
+    assert_eq!(replies[1][0]["result"], serde_json::json!([2]));
+    assert!(outgoing.iter().any(|m| m["method"] == "some/event"));
+}
+
+#[test]
+fn duplex_connections_track_their_in_flight_ids() {
+    let handler = JrpcHandler::new().unwrap();
+    handler
+        .register_method("some/copyParams", CopyParams::new().unwrap())
+        .and_then(|h| h.set_enforce_unique_ids(true))
+        .unwrap();
+
+    let (incoming_tx, incoming_rx) = unbounded::<String>();
+    let (outgoing_tx, outgoing_rx) = unbounded::<String>();
+    let (_notification_tx, notification_rx) = unbounded::<JrpcRequest>();
+
+    incoming_tx
+        .unbounded_send(
+            r#"[
+                {"jsonrpc": "2.0", "method": "some/copyParams", "params": [1], "id": 1},
+                {"jsonrpc": "2.0", "method": "some/copyParams", "params": [2], "id": 1}
+            ]"#
+            .to_string(),
+        )
+        .unwrap();
+    // The id was released once its request was answered
+    incoming_tx
+        .unbounded_send(
+            r#"{"jsonrpc": "2.0", "method": "some/copyParams", "params": [3], "id": 1}"#
+                .to_string(),
+        )
+        .unwrap();
+    drop(incoming_tx);
+
+    serve_duplex(
+        handler,
+        incoming_rx.map_err(|_| ErrorVariant::InternalError),
+        outgoing_tx.sink_map_err(|_| ErrorVariant::InternalError),
+        notification_rx.map_err(|_| ErrorVariant::InternalError),
+    )
+    .wait()
+    .unwrap();
+
+    let replies: Vec<JsonValue> = outgoing_rx
+        .wait()
+        .map(|message| serde_json::from_str(&message.unwrap()).unwrap())
+        .collect();
+    assert_eq!(replies.len(), 2);
+    assert_eq!(replies[0][0]["result"], serde_json::json!([1]));
+    assert_eq!(replies[0][1]["error"]["code"], serde_json::json!(-32600));
+    assert_eq!(replies[1]["result"], serde_json::json!([3]));
+}